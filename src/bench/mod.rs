@@ -0,0 +1,57 @@
+use crate::cli::{Bench, Config};
+use kmeans_rs::kmeans::{self, KMeansError};
+use kmeans_rs::point::generate_clustered_points;
+use kmeans_rs::{Algorithm, DistanceEvaluations, History};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use std::time::Instant;
+
+/// Timing of a single algorithm over the benchmark points.
+#[derive(Debug, Serialize)]
+pub struct BenchRun {
+    pub algorithm: Algorithm,
+    pub seconds: f64,
+    pub iterations: usize,
+    pub inertia: f64,
+    pub distance_evaluations: DistanceEvaluations,
+    /// Baseline time over this algorithm's.
+    pub speedup: f64,
+    /// Whether the final labels are identical to the baseline's.
+    pub matches_baseline: bool,
+}
+
+// cluster the same generated points with every benchmarked algorithm, starting
+// each from the same seed so they share their initial centroids. the first
+// algorithm is the baseline the others are compared against
+pub fn run<R: Rng + ?Sized>(
+    cfg: &Config,
+    bench: &Bench,
+    r: &mut R,
+) -> Result<Vec<BenchRun>, Box<KMeansError>> {
+    let points = generate_clustered_points(r, cfg.bounds(), cfg.k, bench.num_points);
+    let seed: u64 = r.gen();
+
+    let mut runs: Vec<BenchRun> = vec![];
+    let mut baseline: Option<(f64, Vec<usize>)> = None;
+    for algorithm in &bench.algorithms {
+        let params = cfg.kmeans().algorithm(*algorithm).history(History::Final);
+        let start = Instant::now();
+        let result = kmeans::execute(&params, &points, &mut StdRng::seed_from_u64(seed))?;
+        let seconds = start.elapsed().as_secs_f64();
+
+        let labels = result.clustering.labels;
+        let (baseline_seconds, baseline_labels) = baseline.get_or_insert((seconds, labels.clone()));
+        runs.push(BenchRun {
+            algorithm: *algorithm,
+            seconds,
+            iterations: result.convergence.iterations,
+            inertia: result.inertia,
+            distance_evaluations: result.distance_evaluations,
+            speedup: *baseline_seconds / seconds,
+            matches_baseline: *baseline_labels == labels,
+        });
+    }
+
+    Ok(runs)
+}
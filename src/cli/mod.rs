@@ -1,5 +1,11 @@
-use crate::point::{generate_clustered_points, Point};
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
+use kmeans_rs::evaluation::Index;
+use kmeans_rs::point::{generate_labelled_points, Point};
+use kmeans_rs::{
+    Algorithm, Covariance, EmptyCluster, GaussianMixture, History, Init, KMeans, Medoids, Metric,
+    Progress, ToleranceMode,
+};
+use rand::Rng;
 use serde_json::{from_reader, Value};
 use std::error::Error;
 use std::fmt::{Display, Formatter};
@@ -26,6 +32,32 @@ pub struct Config {
     )]
     pub iterations: usize,
 
+    #[clap(
+        long,
+        help = "change in total squared error below which a run has converged",
+        default_value_t = 0.01
+    )]
+    pub tol: f64,
+
+    #[clap(
+        long,
+        help = "whether --tol applies to the absolute or relative change in total squared error: absolute, relative",
+        default_value = "absolute"
+    )]
+    pub tol_mode: ToleranceMode,
+
+    #[clap(
+        long,
+        help = "also converge once no centroid moves farther than this in an iteration"
+    )]
+    pub max_shift: Option<f64>,
+
+    #[clap(
+        long,
+        help = "also converge once an iteration leaves every point in the same cluster"
+    )]
+    pub stop_on_stable_labels: bool,
+
     #[clap(
         short,
         long,
@@ -37,7 +69,7 @@ pub struct Config {
     #[clap(
         short,
         long,
-        help = "path to a JSON file containing input points of the form [{\"x\": 1.1, \"y\": 2.2}, {\"x\": 3.3, \"y\": 4.4}, ...]"
+        help = "path to a JSON file containing input points of the form [[1.1, 2.2], [3.3, 4.4], ...]"
     )]
     pub points_file: Option<PathBuf>,
 
@@ -48,21 +80,237 @@ pub struct Config {
     )]
     pub png_out: PathBuf,
 
+    #[clap(
+        long,
+        help = "initial centroid placement strategy: random, kmeans++, forgy, random-partition",
+        default_value = "random"
+    )]
+    pub init: Init,
+
+    #[clap(
+        long,
+        help = "number of independent initializations to run, keeping the lowest-inertia result",
+        default_value_t = 1
+    )]
+    pub n_init: usize,
+
+    #[clap(
+        long,
+        help = "policy for a centroid left with no points: farthest, split, error",
+        default_value = "farthest"
+    )]
+    pub empty_cluster: EmptyCluster,
+
+    #[clap(
+        long,
+        help = "distance metric: sqeuclidean, manhattan, chebyshev, cosine, minkowski:P",
+        default_value = "sqeuclidean"
+    )]
+    pub metric: Metric,
+
+    #[clap(
+        long,
+        help = "assignment step algorithm: lloyd, elkan, hamerly, minibatch, kdtree",
+        default_value = "lloyd"
+    )]
+    pub algorithm: Algorithm,
+
+    #[clap(
+        long,
+        help = "cluster around medoids picked from the input points instead of centroids: alternating, pam"
+    )]
+    pub medoids: Option<Medoids>,
+
+    #[clap(
+        long,
+        help = "cluster with fuzzy c-means using this fuzzifier, greater than 1 (2 is customary)"
+    )]
+    pub fuzzy: Option<f64>,
+
+    #[clap(
+        long,
+        help = "also fit a Gaussian mixture by EM, starting from the clustering: full, diag, spherical"
+    )]
+    pub gmm: Option<Covariance>,
+
+    #[clap(
+        long,
+        help = "build the k clusters by repeatedly splitting the one with the largest error in two"
+    )]
+    pub bisecting: bool,
+
+    #[clap(long, help = "score the final clustering with per-point silhouettes")]
+    pub silhouette: bool,
+
+    #[clap(
+        long,
+        help = "print a summary of the final clustering's silhouette, Davies-Bouldin, Calinski-Harabasz and Dunn indices, estimating the silhouette and Dunn from --silhouette-sample points when there are more"
+    )]
+    pub metrics: bool,
+
+    #[clap(
+        long,
+        help = "compare the final clustering with the true labels of generated points, or with the 'label' of every input point"
+    )]
+    pub ground_truth: bool,
+
+    #[clap(
+        long,
+        help = "number of points --silhouette and the Dunn index score, sampled at random when there are more",
+        default_value_t = 2000
+    )]
+    pub silhouette_sample: usize,
+
+    #[clap(
+        long,
+        help = "number of points sampled per iteration by --algorithm minibatch",
+        default_value_t = 1024
+    )]
+    pub batch_size: usize,
+
+    #[clap(
+        long,
+        help = "stop --algorithm minibatch after this many batches without improving smoothed inertia",
+        default_value_t = 10
+    )]
+    pub max_no_improvement: usize,
+
+    #[clap(
+        long,
+        help = "seed for the random number generator, chosen at random when omitted"
+    )]
+    pub seed: Option<u64>,
+
+    #[clap(
+        long,
+        help = "iteration history to keep: full, centroids (labels for the final state only), final",
+        default_value = "full"
+    )]
+    pub history: History,
+
+    #[clap(
+        long,
+        help = "number of threads to cluster and render with, defaults to one per CPU"
+    )]
+    pub threads: Option<usize>,
+
     #[clap(long, help = "render output as JSON")]
     pub json_out: bool,
 
-    #[clap(long, help = "lower bound for points", default_value = "0,0")]
+    #[clap(
+        long,
+        help = "dimension of the input points, defaults to that of the bounds or 2"
+    )]
+    pub dim: Option<usize>,
+
+    #[clap(
+        long,
+        help = "lower bound for points, repeated in every dimension if a single value",
+        default_value = "0"
+    )]
     pub lower_bound: Point,
 
-    #[clap(long, help = "upper bound for points", default_value = "1000,1000")]
+    #[clap(
+        long,
+        help = "upper bound for points, repeated in every dimension if a single value",
+        default_value = "1000"
+    )]
     pub upper_bound: Point,
+
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Time each assignment algorithm on the same generated points
+    Bench(Bench),
+    /// Cluster with every k in a range and find the elbow of the inertia curve
+    Sweep(Sweep),
+}
+
+#[derive(Args, Debug)]
+pub struct Bench {
+    #[clap(
+        short,
+        long,
+        help = "number of randomly generated points to cluster",
+        default_value_t = 1_000_000
+    )]
+    pub num_points: usize,
+
+    #[clap(
+        long,
+        help = "comma-separated algorithms to time, the first being the baseline",
+        value_delimiter = ',',
+        default_value = "lloyd,kdtree"
+    )]
+    pub algorithms: Vec<Algorithm>,
+}
+
+#[derive(Args, Debug)]
+pub struct Sweep {
+    #[clap(long, help = "smallest k to try", default_value_t = 1)]
+    pub min_k: usize,
+
+    #[clap(long, help = "largest k to try", default_value_t = 10)]
+    pub max_k: usize,
+
+    #[clap(
+        long,
+        help = "also pick the k scoring best on this index: silhouette, davies-bouldin, calinski-harabasz, dunn"
+    )]
+    pub criterion: Option<Index>,
+
+    #[clap(
+        long,
+        value_name = "B",
+        help = "also pick k by the gap statistic, against B reference datasets drawn uniformly within the bounds"
+    )]
+    pub gap: Option<usize>,
 }
 
 impl Config {
+    // settle on the dimension of the input points and repeat any single-value
+    // bound across all of them, so the bounds always match the points
+    pub fn resolve_dim(&mut self) -> usize {
+        let dim = self.dim.unwrap_or_else(|| {
+            match usize::max(self.lower_bound.dim(), self.upper_bound.dim()) {
+                1 => 2,
+                dim => dim,
+            }
+        });
+
+        for bound in [&mut self.lower_bound, &mut self.upper_bound] {
+            if bound.dim() == 1 {
+                *bound = Point::new(vec![bound.coords[0]; dim]);
+            }
+        }
+
+        *self.dim.insert(dim)
+    }
+
     pub fn validate(&self) -> Result<(), Box<ValidationError>> {
         eprintln!("kmeans-rs: initialized with: {:?}", &self);
 
-        if self.lower_bound.x >= self.upper_bound.x || self.lower_bound.y >= self.upper_bound.y {
+        if self.dim == Some(0) {
+            return Err(ValidationError::new("kmeans-rs: dim must be positive"));
+        }
+
+        let dim = self.dim.unwrap_or_else(|| self.lower_bound.dim());
+        if self.lower_bound.dim() != dim || self.upper_bound.dim() != dim {
+            return Err(ValidationError::new(
+                "kmeans-rs: bounds must have one value per dimension",
+            ));
+        }
+
+        if self
+            .lower_bound
+            .coords
+            .iter()
+            .zip(&self.upper_bound.coords)
+            .any(|(lower, upper)| lower >= upper)
+        {
             return Err(ValidationError::new(
                 "kmeans-rs: lower bounds cannot be greater than upper bounds",
             ));
@@ -78,62 +326,245 @@ impl Config {
             ));
         }
 
+        if self.tol < 0_f64 || matches!(self.max_shift, Some(shift) if shift < 0_f64) {
+            return Err(ValidationError::new(
+                "kmeans-rs: convergence thresholds cannot be negative",
+            ));
+        }
+
+        if self.threads == Some(0) {
+            return Err(ValidationError::new("kmeans-rs: threads must be positive"));
+        }
+
+        if self.n_init < 1 {
+            return Err(ValidationError::new("kmeans-rs: n_init must be positive"));
+        }
+
+        if self.batch_size < 1 {
+            return Err(ValidationError::new(
+                "kmeans-rs: batch size must be positive",
+            ));
+        }
+
+        if self.silhouette_sample < 1 {
+            return Err(ValidationError::new(
+                "kmeans-rs: silhouette sample must be positive",
+            ));
+        }
+
         if self.points_file.is_none() && self.num_points < self.k {
             return Err(ValidationError::new(
                 "kmeans-rs: k is greater than num_points",
             ));
         }
 
+        if let Some(Command::Bench(bench)) = &self.command {
+            if bench.num_points < self.k {
+                return Err(ValidationError::new(
+                    "kmeans-rs: k is greater than the bench num_points",
+                ));
+            }
+
+            if bench.algorithms.is_empty() {
+                return Err(ValidationError::new(
+                    "kmeans-rs: bench needs at least one algorithm",
+                ));
+            }
+        }
+
+        if let Some(Command::Sweep(sweep)) = &self.command {
+            if sweep.min_k < 1 || sweep.max_k <= sweep.min_k {
+                return Err(ValidationError::new(
+                    "kmeans-rs: sweep needs a positive min_k below max_k",
+                ));
+            }
+
+            if self.points_file.is_none() && self.num_points < sweep.max_k {
+                return Err(ValidationError::new(
+                    "kmeans-rs: sweep max_k is greater than num_points",
+                ));
+            }
+
+            if sweep.gap == Some(0) {
+                return Err(ValidationError::new(
+                    "kmeans-rs: the gap statistic needs at least one reference dataset",
+                ));
+            }
+        }
+
         Ok(())
     }
 
+    // the largest k the points will be clustered with: the top of the range
+    // for a sweep, otherwise k itself
+    fn largest_k(&self) -> usize {
+        match &self.command {
+            Some(Command::Sweep(sweep)) => sweep.max_k,
+            _ => self.k,
+        }
+    }
+
+    // k-means parameters for the library, as configured on the command line
+    pub fn kmeans(&self) -> KMeans {
+        self.kmeans_for(self.k)
+    }
+
+    // k-means parameters as configured on the command line, but for k clusters
+    pub fn kmeans_for(&self, k: usize) -> KMeans {
+        let params = KMeans::new(k)
+            .max_iterations(self.iterations)
+            .tolerance(self.tol)
+            .tolerance_mode(self.tol_mode)
+            .stop_when_labels_stable(self.stop_on_stable_labels)
+            .bisecting(self.bisecting)
+            .init(self.init)
+            .n_init(self.n_init)
+            .empty_cluster(self.empty_cluster)
+            .metric(self.metric)
+            .algorithm(self.algorithm)
+            .batch_size(self.batch_size)
+            .max_no_improvement(self.max_no_improvement)
+            .history(self.history)
+            .bounds(self.lower_bound.clone(), self.upper_bound.clone())
+            .progress(report_progress);
+
+        let params = match self.max_shift {
+            Some(max_shift) => params.max_centroid_shift(max_shift),
+            None => params,
+        };
+
+        let params = match self.medoids {
+            Some(medoids) => params.medoids(medoids),
+            None => params,
+        };
+
+        match self.fuzzy {
+            Some(fuzzifier) => params.fuzzy(fuzzifier),
+            None => params,
+        }
+    }
+
+    // Gaussian mixture parameters for the library, when one was asked for
+    pub fn gmm(&self) -> Option<GaussianMixture> {
+        self.gmm.map(GaussianMixture::new)
+    }
+
     pub fn bounds(&self) -> (&Point, &Point) {
         (&self.lower_bound, &self.upper_bound)
     }
 
-    pub fn points(&self) -> Result<Vec<Point>, Box<ValidationError>> {
+    // input points along with their true labels, which generated points always
+    // have and input files may give with a 'label' on every point
+    pub fn labelled_points<R: Rng + ?Sized>(
+        &self,
+        r: &mut R,
+    ) -> Result<LabelledPoints, Box<ValidationError>> {
         if self.points_file.is_none() {
-            return Ok(generate_clustered_points(
-                self.bounds(),
-                self.k,
-                self.num_points,
-            ));
+            let (points, labels) =
+                generate_labelled_points(r, self.bounds(), self.k, self.num_points);
+            return Ok((points, Some(labels)));
         }
 
-        let file = File::open(self.points_file.as_ref().unwrap()).unwrap();
+        let file = File::open(self.points_file.as_ref().unwrap())
+            .map_err(|e| ValidationError::new(&format!("kmeans-rs: {}", e)))?;
         let reader = BufReader::new(file);
-        let input: Vec<Value> = from_reader(reader).unwrap();
+        let input: Vec<Value> =
+            from_reader(reader).map_err(|e| ValidationError::new(&format!("kmeans-rs: {}", e)))?;
 
-        if input.len() < self.k {
-            return Err(ValidationError::new(
-                "kmeans-rs: 'k' greater than points found in input file",
-            ));
+        if input.len() < self.largest_k() {
+            return Err(ValidationError::new(match self.command {
+                Some(Command::Sweep(_)) => {
+                    "kmeans-rs: sweep max_k greater than points found in input file"
+                }
+                _ => "kmeans-rs: 'k' greater than points found in input file",
+            }));
         }
 
-        let points: Vec<Point> = input
+        let points = input
             .iter()
-            .map(|json_val: &Value| {
-                let p = json_val.as_object().unwrap();
-
-                Point {
-                    x: p["x"].as_f64().unwrap(),
-                    y: p["y"].as_f64().unwrap(),
-                }
-            })
-            .collect();
+            .map(parse_point)
+            .collect::<Option<Vec<Point>>>()
+            .ok_or_else(|| {
+                ValidationError::new(
+                    "kmeans-rs: input points must be arrays of numbers, or objects with numeric 'x' and 'y' or a 'coords' array",
+                )
+            })?;
 
         let bounds = self.bounds();
-        if points
-            .iter()
-            .any(|p| p.x < bounds.0.x || p.x >= bounds.1.x || p.y < bounds.0.y || p.y >= bounds.1.y)
-        {
+        if points.iter().any(|p| p.dim() != bounds.0.dim()) {
+            return Err(ValidationError::new(
+                "kmeans-rs: input points do not match the dimension of the bounds, see --dim",
+            ));
+        }
+
+        if points.iter().any(|p| !p.within(bounds)) {
             return Err(ValidationError::new(
                 "kmeans-rs: some input points are out of bounds",
             ));
         }
 
-        Ok(points)
+        let labels: Vec<Option<&Value>> = input.iter().map(|p| p.get("label")).collect();
+        let labels = match labels.iter().filter(|label| label.is_some()).count() {
+            0 => None,
+            n if n == labels.len() => Some(
+                labels
+                    .iter()
+                    .map(|label| label.and_then(Value::as_u64).map(|label| label as usize))
+                    .collect::<Option<Vec<usize>>>()
+                    .ok_or_else(|| {
+                        ValidationError::new(
+                            "kmeans-rs: input point labels must be non-negative integers",
+                        )
+                    })?,
+            ),
+            _ => {
+                return Err(ValidationError::new(
+                    "kmeans-rs: either every input point or none must have a 'label'",
+                ))
+            }
+        };
+
+        Ok((points, labels))
+    }
+}
+
+// print the progress of every restart to stderr
+fn report_progress(progress: Progress) {
+    match progress {
+        Progress::Iteration { restart, iteration } => eprintln!(
+            "kmeans-rs: restart {} calculating iteration {}",
+            restart, iteration
+        ),
+        Progress::Converged {
+            restart,
+            iterations,
+            reason,
+        } => eprintln!(
+            "kmeans-rs: restart {} converged at iteration {} ({:?})",
+            restart, iterations, reason
+        ),
+    }
+}
+
+// input points, and the true label of each when known
+pub type LabelledPoints = (Vec<Point>, Option<Vec<usize>>);
+
+// convert a JSON input point, either an array of coordinates or an object of
+// the form {"x": 1.1, "y": 2.2} or {"coords": [1.1, 2.2, 3.3]}, into a Point
+fn parse_point(json_val: &Value) -> Option<Point> {
+    match json_val {
+        Value::Array(coords) => coords.iter().map(Value::as_f64).collect::<Option<_>>(),
+        Value::Object(p) => match p.get("coords") {
+            Some(Value::Array(coords)) => coords.iter().map(Value::as_f64).collect::<Option<_>>(),
+            Some(_) => None,
+            None => vec![p.get("x")?.as_f64(), p.get("y")?.as_f64()]
+                .into_iter()
+                .collect::<Option<_>>(),
+        },
+        _ => None,
     }
+    .filter(|coords: &Vec<f64>| !coords.is_empty())
+    .map(Point::new)
 }
 
 #[derive(Debug)]
@@ -4,15 +4,36 @@ fn test_cfg() -> Config {
     Config {
         k: 5_usize,
         iterations: 10_usize,
+        tol: 0.01_f64,
+        tol_mode: ToleranceMode::Absolute,
+        max_shift: None,
+        stop_on_stable_labels: false,
         num_points: 100_usize,
         points_file: None,
         png_out: PathBuf::from("/tmp/foobar"),
+        init: Init::Random,
+        n_init: 1,
+        empty_cluster: EmptyCluster::Farthest,
+        metric: Metric::SquaredEuclidean,
+        algorithm: Algorithm::Lloyd,
+        medoids: None,
+        fuzzy: None,
+        gmm: None,
+        bisecting: false,
+        silhouette: false,
+        metrics: false,
+        ground_truth: false,
+        silhouette_sample: 2000,
+        batch_size: 1024,
+        max_no_improvement: 10,
+        seed: None,
+        history: History::Full,
+        threads: None,
         json_out: true,
-        lower_bound: Point { x: 0_f64, y: 0_f64 },
-        upper_bound: Point {
-            x: 20_f64,
-            y: 20_f64,
-        },
+        dim: Some(2),
+        lower_bound: Point::new(vec![0_f64, 0_f64]),
+        upper_bound: Point::new(vec![20_f64, 20_f64]),
+        command: None,
     }
 }
 
@@ -20,19 +41,118 @@ fn test_cfg() -> Config {
 fn test_bounds() {
     let cfg = test_cfg();
     let resolved = cfg.bounds();
-    assert_eq!(0_f64, resolved.0.x);
-    assert_eq!(0_f64, resolved.0.y);
-    assert_eq!(20_f64, resolved.1.x);
-    assert_eq!(20_f64, resolved.1.y);
+    assert_eq!(vec![0_f64, 0_f64], resolved.0.coords);
+    assert_eq!(vec![20_f64, 20_f64], resolved.1.coords);
+}
+
+#[test]
+fn test_resolve_dim() {
+    let mut cfg = Config {
+        dim: Some(8),
+        lower_bound: Point::new(vec![0_f64]),
+        upper_bound: Point::new(vec![20_f64]),
+        ..test_cfg()
+    };
+
+    assert_eq!(8, cfg.resolve_dim());
+    assert_eq!(vec![0_f64; 8], cfg.lower_bound.coords);
+    assert_eq!(vec![20_f64; 8], cfg.upper_bound.coords);
+    assert!(cfg.validate().is_ok());
+
+    let (points, _) = cfg.labelled_points(&mut rand::thread_rng()).unwrap();
+    assert!(points.iter().all(|p| p.dim() == 8));
+}
+
+#[test]
+fn test_resolve_dim_from_bounds() {
+    let mut cfg = Config {
+        dim: None,
+        lower_bound: Point::new(vec![0_f64, 0_f64, 0_f64]),
+        upper_bound: Point::new(vec![20_f64]),
+        ..test_cfg()
+    };
+    assert_eq!(3, cfg.resolve_dim());
+    assert_eq!(vec![20_f64; 3], cfg.upper_bound.coords);
+
+    let mut cfg = Config {
+        dim: None,
+        lower_bound: Point::new(vec![0_f64]),
+        upper_bound: Point::new(vec![20_f64]),
+        ..test_cfg()
+    };
+    assert_eq!(2, cfg.resolve_dim());
+}
+
+#[test]
+fn test_validate_bounds_dim_mismatch() {
+    let cfg = Config {
+        dim: Some(3),
+        ..test_cfg()
+    };
+
+    assert!(cfg.validate().is_err());
+}
+
+#[test]
+fn test_parse_point() {
+    let from_array = parse_point(&serde_json::json!([1.5, 2, 3]));
+    assert_eq!(Some(Point::new(vec![1.5_f64, 2_f64, 3_f64])), from_array);
+
+    let from_object = parse_point(&serde_json::json!({"x": 1, "y": 2.5}));
+    assert_eq!(Some(Point::new(vec![1_f64, 2.5_f64])), from_object);
+
+    assert_eq!(None, parse_point(&serde_json::json!([])));
+    assert_eq!(None, parse_point(&serde_json::json!(["a", 1])));
+    assert_eq!(None, parse_point(&serde_json::json!({"x": 1})));
+
+    let from_coords = parse_point(&serde_json::json!({"coords": [1, 2, 3], "label": 4}));
+    assert_eq!(Some(Point::new(vec![1_f64, 2_f64, 3_f64])), from_coords);
+    assert_eq!(None, parse_point(&serde_json::json!({"coords": 1})));
 }
 
 #[test]
 fn test_points() {
     let cfg = test_cfg();
-    let resolved = cfg.points();
+    let resolved = cfg.labelled_points(&mut rand::thread_rng());
 
     assert!(resolved.is_ok());
-    assert_eq!(cfg.num_points, resolved.unwrap().len());
+    let (points, labels) = resolved.unwrap();
+    assert_eq!(cfg.num_points, points.len());
+    assert_eq!(Some(cfg.num_points), labels.map(|labels| labels.len()));
+}
+
+#[test]
+fn test_points_file_labels() {
+    let load = |name: &str, contents: &str| {
+        let path = std::env::temp_dir().join(format!("kmeans-rs-{}.json", name));
+        std::fs::write(&path, contents).unwrap();
+        let cfg = Config {
+            k: 2,
+            points_file: Some(path.clone()),
+            ..test_cfg()
+        };
+        let loaded = cfg.labelled_points(&mut rand::thread_rng());
+        std::fs::remove_file(path).unwrap();
+        loaded
+    };
+
+    let (points, labels) = load(
+        "labelled",
+        r#"[{"x": 1, "y": 2, "label": 1}, {"coords": [3, 4], "label": 0}]"#,
+    )
+    .unwrap();
+    assert_eq!(Point::new(vec![3_f64, 4_f64]), points[1]);
+    assert_eq!(Some(vec![1, 0]), labels);
+
+    let (_, labels) = load("unlabelled", "[[1, 2], [3, 4]]").unwrap();
+    assert_eq!(None, labels);
+
+    assert!(load(
+        "partly-labelled",
+        r#"[{"x": 1, "y": 2, "label": 1}, [3, 4]]"#
+    )
+    .is_err());
+    assert!(load("bad-label", r#"[{"x": 1, "y": 2, "label": -1}, [3, 4]]"#).is_err());
 }
 
 #[test]
@@ -46,10 +166,7 @@ fn test_validate_good_cfg() {
 #[test]
 fn test_validate_bounds() {
     let cfg = Config {
-        lower_bound: Point {
-            x: 300_f64,
-            y: 300_f64,
-        },
+        lower_bound: Point::new(vec![300_f64, 300_f64]),
         ..test_cfg()
     };
 
@@ -86,3 +203,141 @@ fn test_validate_iterations() {
     let result = cfg.validate();
     assert!(result.is_err());
 }
+
+#[test]
+fn test_validate_n_init() {
+    let cfg = Config {
+        n_init: 0,
+        ..test_cfg()
+    };
+
+    let result = cfg.validate();
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_validate_tol() {
+    let cfg = Config {
+        tol: -1_f64,
+        ..test_cfg()
+    };
+    assert!(cfg.validate().is_err());
+
+    let cfg = Config {
+        max_shift: Some(-1_f64),
+        ..test_cfg()
+    };
+    assert!(cfg.validate().is_err());
+}
+
+#[test]
+fn test_validate_threads() {
+    let cfg = Config {
+        threads: Some(0),
+        ..test_cfg()
+    };
+
+    let result = cfg.validate();
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_validate_batch_size() {
+    let cfg = Config {
+        batch_size: 0,
+        ..test_cfg()
+    };
+
+    assert!(cfg.validate().is_err());
+}
+
+#[test]
+fn test_validate_silhouette_sample() {
+    let cfg = Config {
+        silhouette_sample: 0,
+        ..test_cfg()
+    };
+
+    assert!(cfg.validate().is_err());
+}
+
+#[test]
+fn test_validate_bench() {
+    let bench = |num_points: usize, algorithms: Vec<Algorithm>| Config {
+        command: Some(Command::Bench(Bench {
+            num_points,
+            algorithms,
+        })),
+        ..test_cfg()
+    };
+
+    assert!(bench(1000, vec![Algorithm::Lloyd, Algorithm::KdTree])
+        .validate()
+        .is_ok());
+    assert!(bench(3, vec![Algorithm::Lloyd]).validate().is_err());
+    assert!(bench(1000, vec![]).validate().is_err());
+}
+
+#[test]
+fn test_parse_bench() {
+    let cfg = Config::parse_from([
+        "kmeans-rs",
+        "-k",
+        "8",
+        "bench",
+        "--algorithms",
+        "lloyd,elkan",
+    ]);
+    assert_eq!(8, cfg.k);
+    match cfg.command {
+        Some(Command::Bench(bench)) => {
+            assert_eq!(1_000_000, bench.num_points);
+            assert_eq!(vec![Algorithm::Lloyd, Algorithm::Elkan], bench.algorithms);
+        }
+        _ => panic!("expected the bench subcommand"),
+    }
+}
+
+#[test]
+fn test_validate_sweep() {
+    let sweep = |min_k: usize, max_k: usize, gap: Option<usize>| Config {
+        command: Some(Command::Sweep(Sweep {
+            min_k,
+            max_k,
+            criterion: None,
+            gap,
+        })),
+        ..test_cfg()
+    };
+
+    assert!(sweep(1, 10, None).validate().is_ok());
+    assert!(sweep(0, 10, None).validate().is_err());
+    assert!(sweep(4, 4, None).validate().is_err());
+    assert!(sweep(2, 101, None).validate().is_err());
+    assert!(sweep(1, 10, Some(10)).validate().is_ok());
+    assert!(sweep(1, 10, Some(0)).validate().is_err());
+}
+
+#[test]
+fn test_sweep_points_file_size() {
+    // a sweep needs as many input points as the top of its range, whatever k is
+    let path = std::env::temp_dir().join("kmeans-rs-sweep.json");
+    std::fs::write(&path, "[[1, 2], [3, 4], [5, 6]]").unwrap();
+    let sweep = |max_k: usize| Config {
+        k: 2,
+        points_file: Some(path.clone()),
+        command: Some(Command::Sweep(Sweep {
+            min_k: 1,
+            max_k,
+            criterion: None,
+            gap: None,
+        })),
+        ..test_cfg()
+    };
+
+    let fits = sweep(3).labelled_points(&mut rand::thread_rng()).is_ok();
+    let too_few = sweep(4).labelled_points(&mut rand::thread_rng()).is_err();
+    std::fs::remove_file(path).unwrap();
+    assert!(fits);
+    assert!(too_few);
+}
@@ -0,0 +1,310 @@
+use crate::point::Point;
+use serde::{Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Dissimilarity between points, along with the cluster center it implies.
+///
+/// k-means assigns each point to the centroid with the smallest
+/// [`Distance::distance`], then moves each centroid to the [`Distance::center`]
+/// of its members. The center should minimize the total distance to the
+/// members, which is the mean only for squared Euclidean distance.
+pub trait Distance: Send + Sync {
+    /// Dissimilarity between `a` and `b`, which share a dimension.
+    fn distance(&self, a: &Point, b: &Point) -> f64;
+
+    /// Point minimizing the total distance to every member of a non-empty
+    /// cluster. Defaults to the mean.
+    fn center(&self, cluster: &[&Point]) -> Point {
+        mean(cluster)
+    }
+}
+
+/// Squared Euclidean distance, the classic k-means objective. Centers are means.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SquaredEuclidean;
+
+impl Distance for SquaredEuclidean {
+    fn distance(&self, a: &Point, b: &Point) -> f64 {
+        a.sum_squared_error(b)
+    }
+}
+
+/// Manhattan (L1) distance. Centers are coordinate-wise medians.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Manhattan;
+
+impl Distance for Manhattan {
+    fn distance(&self, a: &Point, b: &Point) -> f64 {
+        a.coords
+            .iter()
+            .zip(&b.coords)
+            .fold(0_f64, |acc, (x, y)| acc + (x - y).abs())
+    }
+
+    fn center(&self, cluster: &[&Point]) -> Point {
+        median(cluster)
+    }
+}
+
+/// Chebyshev (L∞) distance. There is no closed form for its center, which is
+/// approximated by coordinate descent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chebyshev;
+
+impl Distance for Chebyshev {
+    fn distance(&self, a: &Point, b: &Point) -> f64 {
+        a.coords
+            .iter()
+            .zip(&b.coords)
+            .fold(0_f64, |acc, (x, y)| f64::max(acc, (x - y).abs()))
+    }
+
+    fn center(&self, cluster: &[&Point]) -> Point {
+        minimize_total_distance(self, cluster)
+    }
+}
+
+/// Cosine distance, one minus the cosine of the angle between two points as
+/// seen from the origin. Centers are the normalized mean direction of the
+/// members, as in spherical k-means.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cosine;
+
+impl Distance for Cosine {
+    fn distance(&self, a: &Point, b: &Point) -> f64 {
+        let norms = norm(a) * norm(b);
+        if norms == 0_f64 {
+            // the angle to the origin is undefined, treat it as orthogonal
+            return 1_f64;
+        }
+
+        let dot = a
+            .coords
+            .iter()
+            .zip(&b.coords)
+            .fold(0_f64, |acc, (x, y)| acc + x * y);
+        f64::max(0_f64, 1_f64 - dot / norms)
+    }
+
+    fn center(&self, cluster: &[&Point]) -> Point {
+        let directions: Vec<Point> = cluster
+            .iter()
+            .map(|p| match norm(p) {
+                n if n > 0_f64 => Point::new(p.coords.iter().map(|c| c / n).collect()),
+                _ => (*p).clone(),
+            })
+            .collect();
+        let center = mean(&directions.iter().collect::<Vec<_>>());
+
+        match norm(&center) {
+            n if n > 0_f64 => Point::new(center.coords.iter().map(|c| c / n).collect()),
+            _ => mean(cluster),
+        }
+    }
+}
+
+/// Minkowski distance of finite order `p`, at least 1. Order 1 is Manhattan distance
+/// and its median center is used; other orders approximate the center by
+/// coordinate descent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Minkowski {
+    pub p: f64,
+}
+
+impl Distance for Minkowski {
+    fn distance(&self, a: &Point, b: &Point) -> f64 {
+        a.coords
+            .iter()
+            .zip(&b.coords)
+            .fold(0_f64, |acc, (x, y)| acc + (x - y).abs().powf(self.p))
+            .powf(1_f64 / self.p)
+    }
+
+    fn center(&self, cluster: &[&Point]) -> Point {
+        match self.p == 1_f64 {
+            true => median(cluster),
+            false => minimize_total_distance(self, cluster),
+        }
+    }
+}
+
+/// Built-in distance metric, selectable by name.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    /// The default, and the only metric whose inertia is a total squared error.
+    SquaredEuclidean,
+    Manhattan,
+    Chebyshev,
+    Cosine,
+    /// Minkowski distance of the given finite order, at least 1. The limit of
+    /// infinite order is [`Metric::Chebyshev`].
+    Minkowski(f64),
+}
+
+impl Distance for Metric {
+    fn distance(&self, a: &Point, b: &Point) -> f64 {
+        match self {
+            Metric::SquaredEuclidean => SquaredEuclidean.distance(a, b),
+            Metric::Manhattan => Manhattan.distance(a, b),
+            Metric::Chebyshev => Chebyshev.distance(a, b),
+            Metric::Cosine => Cosine.distance(a, b),
+            Metric::Minkowski(p) => Minkowski { p: *p }.distance(a, b),
+        }
+    }
+
+    fn center(&self, cluster: &[&Point]) -> Point {
+        match self {
+            Metric::SquaredEuclidean => SquaredEuclidean.center(cluster),
+            Metric::Manhattan => Manhattan.center(cluster),
+            Metric::Chebyshev => Chebyshev.center(cluster),
+            Metric::Cosine => Cosine.center(cluster),
+            Metric::Minkowski(p) => Minkowski { p: *p }.center(cluster),
+        }
+    }
+}
+
+impl Display for Metric {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Metric::SquaredEuclidean => write!(f, "sqeuclidean"),
+            Metric::Manhattan => write!(f, "manhattan"),
+            Metric::Chebyshev => write!(f, "chebyshev"),
+            Metric::Cosine => write!(f, "cosine"),
+            Metric::Minkowski(p) => write!(f, "minkowski:{}", p),
+        }
+    }
+}
+
+impl Serialize for Metric {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+// convert raw String input of the form "manhattan" or "minkowski:3" into Metric
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sqeuclidean" => Ok(Metric::SquaredEuclidean),
+            "manhattan" => Ok(Metric::Manhattan),
+            "chebyshev" => Ok(Metric::Chebyshev),
+            "cosine" => Ok(Metric::Cosine),
+            _ => match s.strip_prefix("minkowski:").map(f64::from_str) {
+                Some(Ok(p)) if p.is_finite() && p >= 1_f64 => Ok(Metric::Minkowski(p)),
+                Some(_) => Err(format!(
+                    "invalid minkowski order in '{}', expected a finite number of at least 1 (use chebyshev for the limit)",
+                    s
+                )),
+                None => Err(format!(
+                    "unknown metric '{}', expected one of: sqeuclidean, manhattan, chebyshev, cosine, minkowski:P",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+fn norm(p: &Point) -> f64 {
+    p.coords.iter().map(|c| c * c).sum::<f64>().sqrt()
+}
+
+// coordinate-wise mean of a non-empty cluster. points are summed in input order
+// so the result does not depend on how the caller parallelizes
+fn mean(cluster: &[&Point]) -> Point {
+    let size = cluster.len() as f64;
+    let sum = cluster
+        .iter()
+        .fold(Point::zero(cluster[0].dim()), |mut acc, v| {
+            acc.coords
+                .iter_mut()
+                .zip(&v.coords)
+                .for_each(|(a, c)| *a += c);
+            acc
+        });
+
+    Point::new(sum.coords.iter().map(|c| c / size).collect())
+}
+
+// coordinate-wise median of a non-empty cluster, which minimizes the total
+// Manhattan distance to its members
+fn median(cluster: &[&Point]) -> Point {
+    let dim = cluster[0].dim();
+    Point::new(
+        (0..dim)
+            .map(|d| {
+                let mut values: Vec<f64> = cluster.iter().map(|p| p.coords[d]).collect();
+                values.sort_by(f64::total_cmp);
+
+                let mid = values.len() / 2;
+                match values.len() % 2 {
+                    0 => (values[mid - 1] + values[mid]) / 2_f64,
+                    _ => values[mid],
+                }
+            })
+            .collect(),
+    )
+}
+
+const DESCENT_SWEEPS: usize = 4;
+const SEARCH_STEPS: usize = 32;
+
+// approximate the center of metrics without a closed form by coordinate
+// descent from the better of the mean and the median: each coordinate in turn
+// is golden-section searched between the members' extremes while the others
+// stay fixed. the total distance is convex in every coordinate, and a step is
+// only kept when it improves the total, so the result is never worse than the
+// starting point
+fn minimize_total_distance<D: Distance + ?Sized>(metric: &D, cluster: &[&Point]) -> Point {
+    let total = |c: &Point| {
+        cluster
+            .iter()
+            .fold(0_f64, |acc, p| acc + metric.distance(c, p))
+    };
+    let ratio = (5_f64.sqrt() - 1_f64) / 2_f64;
+
+    let (mean, median) = (mean(cluster), median(cluster));
+    let (mut center, mut best) = match (total(&mean), total(&median)) {
+        (a, b) if a <= b => (mean, a),
+        (_, b) => (median, b),
+    };
+    for _ in 0..DESCENT_SWEEPS {
+        let before = best;
+        for d in 0..center.dim() {
+            let at = |center: &Point, v: f64| {
+                let mut candidate = center.clone();
+                candidate.coords[d] = v;
+                total(&candidate)
+            };
+
+            let mut lo = cluster.iter().map(|p| p.coords[d]).fold(f64::MAX, f64::min);
+            let mut hi = cluster.iter().map(|p| p.coords[d]).fold(f64::MIN, f64::max);
+            for _ in 0..SEARCH_STEPS {
+                let a = hi - ratio * (hi - lo);
+                let b = lo + ratio * (hi - lo);
+                match at(&center, a) < at(&center, b) {
+                    true => hi = b,
+                    false => lo = a,
+                }
+            }
+
+            let v = (lo + hi) / 2_f64;
+            let candidate = at(&center, v);
+            if candidate < best {
+                center.coords[d] = v;
+                best = candidate;
+            }
+        }
+
+        if best >= before {
+            break;
+        }
+    }
+
+    center
+}
+
+#[cfg(test)]
+mod test;
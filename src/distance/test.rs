@@ -0,0 +1,142 @@
+use super::*;
+
+fn pair() -> (Point, Point) {
+    (
+        Point::new(vec![1_f64, 2_f64, 3_f64]),
+        Point::new(vec![4_f64, -2_f64, 3_f64]),
+    )
+}
+
+#[test]
+fn test_squared_euclidean() {
+    let (a, b) = pair();
+
+    assert_eq!(25_f64, SquaredEuclidean.distance(&a, &b));
+    assert_eq!(0_f64, SquaredEuclidean.distance(&a, &a));
+}
+
+#[test]
+fn test_manhattan() {
+    let (a, b) = pair();
+
+    assert_eq!(7_f64, Manhattan.distance(&a, &b));
+}
+
+#[test]
+fn test_chebyshev() {
+    let (a, b) = pair();
+
+    assert_eq!(4_f64, Chebyshev.distance(&a, &b));
+}
+
+#[test]
+fn test_minkowski() {
+    let (a, b) = pair();
+
+    assert!((Minkowski { p: 1_f64 }.distance(&a, &b) - 7_f64).abs() < 1e-9);
+    assert!((Minkowski { p: 2_f64 }.distance(&a, &b) - 5_f64).abs() < 1e-9);
+
+    let p3 = (27_f64 + 64_f64).powf(1_f64 / 3_f64);
+    assert!((Minkowski { p: 3_f64 }.distance(&a, &b) - p3).abs() < 1e-9);
+}
+
+#[test]
+fn test_cosine() {
+    let a = Point::new(vec![1_f64, 0_f64]);
+    let b = Point::new(vec![0_f64, 5_f64]);
+    let c = Point::new(vec![3_f64, 0_f64]);
+    let d = Point::new(vec![-2_f64, 0_f64]);
+
+    assert!((Cosine.distance(&a, &b) - 1_f64).abs() < 1e-9);
+    assert!(Cosine.distance(&a, &c).abs() < 1e-9);
+    assert!((Cosine.distance(&a, &d) - 2_f64).abs() < 1e-9);
+    assert_eq!(1_f64, Cosine.distance(&a, &Point::zero(2)));
+}
+
+#[test]
+fn test_squared_euclidean_center_is_mean() {
+    let points = [
+        Point::new(vec![0_f64, 0_f64]),
+        Point::new(vec![20_f64, 40_f64]),
+    ];
+
+    let center = SquaredEuclidean.center(&points.iter().collect::<Vec<_>>());
+    assert_eq!(Point::new(vec![10_f64, 20_f64]), center);
+}
+
+#[test]
+fn test_manhattan_center_is_median() {
+    let points = [
+        Point::new(vec![0_f64, 9_f64]),
+        Point::new(vec![1_f64, 1_f64]),
+        Point::new(vec![50_f64, 2_f64]),
+        Point::new(vec![3_f64, 0_f64]),
+    ];
+
+    let center = Manhattan.center(&points.iter().collect::<Vec<_>>());
+    assert_eq!(Point::new(vec![2_f64, 1.5_f64]), center);
+}
+
+#[test]
+fn test_cosine_center_is_mean_direction() {
+    let points = [
+        Point::new(vec![10_f64, 0_f64]),
+        Point::new(vec![0_f64, 0.1_f64]),
+    ];
+
+    let center = Cosine.center(&points.iter().collect::<Vec<_>>());
+    let expected = 1_f64 / 2_f64.sqrt();
+    assert_eq!(Point::new(vec![expected, expected]), center);
+}
+
+#[test]
+fn test_approximate_centers_improve_on_mean() {
+    let points = [
+        Point::new(vec![0_f64, 0_f64]),
+        Point::new(vec![1_f64, 0_f64]),
+        Point::new(vec![0_f64, 1_f64]),
+        Point::new(vec![100_f64, 100_f64]),
+    ];
+    let cluster: Vec<&Point> = points.iter().collect();
+    let total = |metric: &dyn Distance, c: &Point| -> f64 {
+        cluster.iter().map(|p| metric.distance(c, p)).sum()
+    };
+
+    let metrics: [&dyn Distance; 2] = [&Chebyshev, &Minkowski { p: 3_f64 }];
+    for metric in metrics {
+        let center = metric.center(&cluster);
+        assert!(total(metric, &center) < total(metric, &mean(&cluster)));
+    }
+}
+
+#[test]
+fn test_metric_from_str() {
+    assert_eq!(
+        Metric::SquaredEuclidean,
+        Metric::from_str("sqeuclidean").unwrap()
+    );
+    assert_eq!(Metric::Manhattan, Metric::from_str("manhattan").unwrap());
+    assert_eq!(Metric::Chebyshev, Metric::from_str("chebyshev").unwrap());
+    assert_eq!(Metric::Cosine, Metric::from_str("cosine").unwrap());
+    assert_eq!(
+        Metric::Minkowski(3_f64),
+        Metric::from_str("minkowski:3").unwrap()
+    );
+    assert!(Metric::from_str("minkowski:0.5").is_err());
+    assert!(Metric::from_str("minkowski:inf").is_err());
+    assert!(Metric::from_str("minkowski:NaN").is_err());
+    assert!(Metric::from_str("minkowski:foo").is_err());
+    assert!(Metric::from_str("foobar").is_err());
+}
+
+#[test]
+fn test_metric_serializes_as_name() {
+    assert_eq!(
+        "\"minkowski:2.5\"",
+        serde_json::to_string(&Metric::Minkowski(2.5)).unwrap()
+    );
+    assert_eq!(
+        "\"cosine\"",
+        serde_json::to_string(&Metric::Cosine).unwrap()
+    );
+}
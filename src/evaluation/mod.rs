@@ -0,0 +1,633 @@
+//! Measures of how well a clustering fits its input points, and of how well
+//! it agrees with their true classes when those are known.
+
+use crate::distance::{Distance, Metric};
+use crate::kmeans::Clustering;
+use crate::point::Point;
+use rand::seq::index;
+use rand::Rng;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Internal validity index, judging a clustering from its input points alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Index {
+    /// Mean [`Silhouette`] of the points, higher is better.
+    #[serde(rename = "silhouette")]
+    Silhouette,
+    /// [`davies_bouldin`], lower is better.
+    #[serde(rename = "davies_bouldin")]
+    DaviesBouldin,
+    /// [`calinski_harabasz`], higher is better.
+    #[serde(rename = "calinski_harabasz")]
+    CalinskiHarabasz,
+    /// [`dunn`], higher is better.
+    #[serde(rename = "dunn")]
+    Dunn,
+}
+
+impl Index {
+    /// Every index, in the order they are reported.
+    pub const ALL: [Index; 4] = [
+        Index::Silhouette,
+        Index::DaviesBouldin,
+        Index::CalinskiHarabasz,
+        Index::Dunn,
+    ];
+
+    /// Whether a higher value of the index means a better clustering.
+    pub fn higher_is_better(&self) -> bool {
+        !matches!(self, Index::DaviesBouldin)
+    }
+
+    /// Value of the index for a clustering of points under metric, none where
+    /// it is undefined, such as for a single cluster. The silhouette score and
+    /// Dunn index are estimated from a sample of `sample_size` points when
+    /// there are more, see [`silhouette_sampled`] and [`dunn_sampled`].
+    pub fn score<R: Rng + ?Sized>(
+        &self,
+        metric: &Metric,
+        points: &[Point],
+        clustering: &Clustering,
+        sample_size: usize,
+        r: &mut R,
+    ) -> Option<f64> {
+        match self {
+            Index::Silhouette => {
+                let clusters = clustering.members(points);
+                match clusters.iter().filter(|c| !c.is_empty()).count() {
+                    0 | 1 => None,
+                    _ => Some(
+                        silhouette_sampled(metric, points, &clustering.labels, sample_size, r)
+                            .score,
+                    ),
+                }
+            }
+            Index::DaviesBouldin => davies_bouldin(metric, points, clustering),
+            Index::CalinskiHarabasz => calinski_harabasz(points, clustering),
+            Index::Dunn => dunn_sampled(metric, points, &clustering.labels, sample_size, r),
+        }
+    }
+}
+
+impl Display for Index {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Index::Silhouette => write!(f, "silhouette"),
+            Index::DaviesBouldin => write!(f, "davies-bouldin"),
+            Index::CalinskiHarabasz => write!(f, "calinski-harabasz"),
+            Index::Dunn => write!(f, "dunn"),
+        }
+    }
+}
+
+// convert raw String input of the form "davies-bouldin" into Index
+impl FromStr for Index {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "silhouette" => Ok(Index::Silhouette),
+            "davies-bouldin" => Ok(Index::DaviesBouldin),
+            "calinski-harabasz" => Ok(Index::CalinskiHarabasz),
+            "dunn" => Ok(Index::Dunn),
+            _ => Err(format!(
+                "unknown index '{}', expected one of: silhouette, davies-bouldin, calinski-harabasz, dunn",
+                s
+            )),
+        }
+    }
+}
+
+/// Silhouettes of a clustering, from Rousseeuw's "Silhouettes: a Graphical Aid
+/// to the Interpretation and Validation of Cluster Analysis" (1987).
+///
+/// A point's silhouette compares `a`, its mean distance to the other members
+/// of its cluster, with `b`, its mean distance to the members of the nearest
+/// other cluster, as `(b - a) / max(a, b)`. It ranges from -1 to 1: near 1 the
+/// point sits well inside its cluster, near 0 it lies between two clusters,
+/// and below 0 it is closer to another cluster than its own. Points alone in
+/// their cluster, or in the only cluster, have a silhouette of 0.
+///
+/// Distances are measured with the clustering's metric, except that squared
+/// Euclidean distance is replaced by plain Euclidean distance, as is customary.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Silhouette {
+    /// Silhouette of each input point, none for points left out of a sample.
+    pub points: Vec<Option<f64>>,
+    /// Mean silhouette of each cluster's scored points, none for a cluster
+    /// without any.
+    pub clusters: Vec<Option<f64>>,
+    /// Mean silhouette of every scored point.
+    pub score: f64,
+    /// Whether only a sample of the points was scored.
+    pub sampled: bool,
+}
+
+/// Silhouette of every input point, given the cluster each belongs to. Takes
+/// time quadratic in the number of points, see [`silhouette_sampled`] for
+/// large inputs.
+///
+/// ```
+/// use kmeans_rs::evaluation::silhouette;
+/// use kmeans_rs::{Metric, Point};
+///
+/// let points = vec![
+///     Point::new(vec![0.0, 0.0]),
+///     Point::new(vec![0.0, 1.0]),
+///     Point::new(vec![10.0, 0.0]),
+///     Point::new(vec![10.0, 1.0]),
+/// ];
+///
+/// let silhouette = silhouette(&Metric::SquaredEuclidean, &points, &[0, 0, 1, 1]);
+/// assert!(silhouette.score > 0.9);
+/// assert_eq!(2, silhouette.clusters.len());
+/// assert!((silhouette.points[0].unwrap() - 0.9).abs() < 0.01);
+/// ```
+pub fn silhouette(metric: &Metric, points: &[Point], labels: &[usize]) -> Silhouette {
+    let scored: Vec<usize> = (0..points.len()).collect();
+    score_silhouettes(metric, points, labels, &scored, false)
+}
+
+/// Silhouette of a uniform sample of `sample_size` input points, each still
+/// measured against every point, so that the overall score estimates the
+/// exact one in time linear in the number of points. Every point is scored
+/// when there are no more than `sample_size`.
+pub fn silhouette_sampled<R: Rng + ?Sized>(
+    metric: &Metric,
+    points: &[Point],
+    labels: &[usize],
+    sample_size: usize,
+    r: &mut R,
+) -> Silhouette {
+    if sample_size >= points.len() {
+        return silhouette(metric, points, labels);
+    }
+
+    let mut sample = index::sample(r, points.len(), sample_size).into_vec();
+    sample.sort_unstable();
+    score_silhouettes(metric, points, labels, &sample, true)
+}
+
+// silhouettes of the scored points against every point. per-point sums run in
+// input order, independent of the number of threads
+fn score_silhouettes(
+    metric: &Metric,
+    points: &[Point],
+    labels: &[usize],
+    scored: &[usize],
+    sampled: bool,
+) -> Silhouette {
+    let k = labels.iter().max().map_or(0, |label| label + 1);
+    let mut sizes = vec![0_usize; k];
+    labels.iter().for_each(|label| sizes[*label] += 1);
+
+    let values: Vec<f64> = scored
+        .par_iter()
+        .map(|ndx| {
+            let point = &points[*ndx];
+            let own = labels[*ndx];
+            if sizes[own] < 2 {
+                return 0_f64;
+            }
+
+            let mut totals = vec![0_f64; k];
+            for (other, label) in points.iter().zip(labels) {
+                totals[*label] += dissimilarity(metric, point, other);
+            }
+            totals[own] -= dissimilarity(metric, point, point);
+
+            let a = totals[own] / (sizes[own] - 1) as f64;
+            let b = (0..k)
+                .filter(|label| *label != own && sizes[*label] > 0)
+                .map(|label| totals[label] / sizes[label] as f64)
+                .fold(f64::MAX, f64::min);
+            match b == f64::MAX || f64::max(a, b) <= 0_f64 {
+                true => 0_f64,
+                false => (b - a) / f64::max(a, b),
+            }
+        })
+        .collect();
+
+    let mut sums = vec![0_f64; k];
+    let mut counts = vec![0_usize; k];
+    let mut silhouettes = vec![None; points.len()];
+    for (ndx, value) in scored.iter().zip(&values) {
+        sums[labels[*ndx]] += value;
+        counts[labels[*ndx]] += 1;
+        silhouettes[*ndx] = Some(*value);
+    }
+
+    Silhouette {
+        points: silhouettes,
+        clusters: sums
+            .iter()
+            .zip(&counts)
+            .map(|(sum, count)| match count {
+                0 => None,
+                _ => Some(sum / *count as f64),
+            })
+            .collect(),
+        score: match values.len() {
+            0 => 0_f64,
+            n => values.iter().fold(0_f64, |acc, v| acc + v) / n as f64,
+        },
+        sampled,
+    }
+}
+
+/// Davies-Bouldin index of a clustering, from Davies and Bouldin's "A Cluster
+/// Separation Measure" (1979). Every cluster is paired with the other cluster
+/// it is most similar to, similarity being the sum of the two clusters' mean
+/// distances to their centroids over the distance between the centroids, and
+/// the index is the mean of those similarities. Lower is better, 0 being
+/// clusters shrunk to their centroids.
+///
+/// Distances are measured as for [`Silhouette`]. Empty clusters are ignored,
+/// as are pairs of clusters whose centroids coincide, which have no
+/// separation to compare with. There is no index without at least two
+/// non-empty clusters with distinct centroids.
+pub fn davies_bouldin(metric: &Metric, points: &[Point], clustering: &Clustering) -> Option<f64> {
+    let k = clustering.centroids.len();
+    let mut spreads = vec![0_f64; k];
+    let mut sizes = vec![0_usize; k];
+    for (point, label) in points.iter().zip(&clustering.labels) {
+        spreads[*label] += dissimilarity(metric, &clustering.centroids[*label], point);
+        sizes[*label] += 1;
+    }
+
+    let clusters: Vec<usize> = (0..k).filter(|label| sizes[*label] > 0).collect();
+    if clusters.len() < 2 {
+        return None;
+    }
+    clusters
+        .iter()
+        .for_each(|label| spreads[*label] /= sizes[*label] as f64);
+
+    let mut separated = false;
+    let total = clusters.iter().fold(0_f64, |acc, i| {
+        let similarity = clusters
+            .iter()
+            .filter(|j| *j != i)
+            .filter_map(|j| {
+                let separation =
+                    dissimilarity(metric, &clustering.centroids[*i], &clustering.centroids[*j]);
+                match separation > 0_f64 {
+                    true => Some((spreads[*i] + spreads[*j]) / separation),
+                    false => None,
+                }
+            })
+            .inspect(|_| separated = true)
+            .fold(0_f64, f64::max);
+        acc + similarity
+    });
+
+    match separated {
+        true => Some(total / clusters.len() as f64),
+        false => None,
+    }
+}
+
+/// Calinski-Harabasz index of a clustering, from Caliński and Harabasz's "A
+/// Dendrite Method for Cluster Analysis" (1974): the ratio of the squared
+/// Euclidean dispersion between the cluster means to that within clusters,
+/// each divided by its degrees of freedom. Higher is better.
+///
+/// The index is defined in terms of means whatever the clustering's metric.
+/// Empty clusters are ignored, and there is no index without at least two
+/// non-empty clusters, more points than clusters, and some dispersion within
+/// the clusters.
+pub fn calinski_harabasz(points: &[Point], clustering: &Clustering) -> Option<f64> {
+    let k = clustering.centroids.len();
+    let dim = points.first().map_or(0, Point::dim);
+    let mut sums = vec![Point::zero(dim); k];
+    let mut sizes = vec![0_usize; k];
+    for (point, label) in points.iter().zip(&clustering.labels) {
+        sums[*label]
+            .coords
+            .iter_mut()
+            .zip(&point.coords)
+            .for_each(|(s, c)| *s += c);
+        sizes[*label] += 1;
+    }
+
+    let clusters = sizes.iter().filter(|size| **size > 0).count();
+    if clusters < 2 || points.len() <= clusters {
+        return None;
+    }
+
+    let mean_of =
+        |sum: &Point, size: usize| Point::new(sum.coords.iter().map(|s| s / size as f64).collect());
+    let total = sums.iter().fold(Point::zero(dim), |acc, sum| {
+        Point::new(
+            acc.coords
+                .iter()
+                .zip(&sum.coords)
+                .map(|(a, s)| a + s)
+                .collect(),
+        )
+    });
+    let mean = mean_of(&total, points.len());
+    let means: Vec<Point> = sums
+        .iter()
+        .zip(&sizes)
+        .map(|(sum, size)| mean_of(sum, usize::max(*size, 1)))
+        .collect();
+
+    let between = means.iter().zip(&sizes).fold(0_f64, |acc, (m, size)| {
+        acc + *size as f64 * m.sum_squared_error(&mean)
+    });
+    let within = points
+        .iter()
+        .zip(&clustering.labels)
+        .fold(0_f64, |acc, (point, label)| {
+            acc + point.sum_squared_error(&means[*label])
+        });
+    if within <= 0_f64 {
+        return None;
+    }
+
+    Some((between / (clusters - 1) as f64) / (within / (points.len() - clusters) as f64))
+}
+
+/// Dunn index of a clustering, from Dunn's "A Fuzzy Relative of the ISODATA
+/// Process and Its Use in Detecting Compact Well-Separated Clusters" (1973):
+/// the smallest distance between points of different clusters over the
+/// largest distance between points of the same cluster. Higher is better.
+///
+/// Distances are measured as for [`Silhouette`], between every pair of
+/// points, so this takes time quadratic in the number of points, see
+/// [`dunn_sampled`] for large inputs. There is no index without at least two
+/// non-empty clusters, one of them holding two distinct points.
+pub fn dunn(metric: &Metric, points: &[Point], labels: &[usize]) -> Option<f64> {
+    // smallest separation and largest diameter, from each point to those
+    // after it. both folds give the same result in any order
+    let (separation, diameter) = (0..points.len())
+        .into_par_iter()
+        .map(|i| {
+            points[i + 1..].iter().zip(&labels[i + 1..]).fold(
+                (f64::MAX, 0_f64),
+                |(separation, diameter), (other, label)| {
+                    let d = dissimilarity(metric, &points[i], other);
+                    match *label == labels[i] {
+                        true => (separation, f64::max(diameter, d)),
+                        false => (f64::min(separation, d), diameter),
+                    }
+                },
+            )
+        })
+        .reduce(
+            || (f64::MAX, 0_f64),
+            |a, b| (f64::min(a.0, b.0), f64::max(a.1, b.1)),
+        );
+
+    match separation < f64::MAX && diameter > 0_f64 {
+        true => Some(separation / diameter),
+        false => None,
+    }
+}
+
+/// Dunn index of a uniform sample of `sample_size` input points, estimating
+/// the exact index in time quadratic in the sample size rather than the number
+/// of points. A sample can only miss the closest pair of points in different
+/// clusters and the farthest pair in the same one, so the estimate is never
+/// below the exact index. Every point is used when there are no more than
+/// `sample_size`.
+pub fn dunn_sampled<R: Rng + ?Sized>(
+    metric: &Metric,
+    points: &[Point],
+    labels: &[usize],
+    sample_size: usize,
+    r: &mut R,
+) -> Option<f64> {
+    if sample_size >= points.len() {
+        return dunn(metric, points, labels);
+    }
+
+    let mut sample = index::sample(r, points.len(), sample_size).into_vec();
+    sample.sort_unstable();
+    let sampled_points: Vec<Point> = sample.iter().map(|ndx| points[*ndx].clone()).collect();
+    let sampled_labels: Vec<usize> = sample.iter().map(|ndx| labels[*ndx]).collect();
+    dunn(metric, &sampled_points, &sampled_labels)
+}
+
+/// Table counting the points each true class shares with each cluster.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Contingency {
+    /// Distinct true labels, in ascending order, one per row.
+    pub classes: Vec<usize>,
+    /// Distinct cluster labels, in ascending order, one per column.
+    pub clusters: Vec<usize>,
+    /// Number of points in both the class of each row and the cluster of each
+    /// column.
+    pub counts: Vec<Vec<usize>>,
+}
+
+impl Contingency {
+    /// Cross tabulate the true label of every point against its cluster label.
+    ///
+    /// # Panics
+    ///
+    /// If `truth` and `labels` differ in length.
+    pub fn new(truth: &[usize], labels: &[usize]) -> Self {
+        assert_eq!(
+            truth.len(),
+            labels.len(),
+            "kmeans-rs: every point needs both a true and a cluster label"
+        );
+
+        let distinct = |labels: &[usize]| {
+            let mut distinct = labels.to_vec();
+            distinct.sort_unstable();
+            distinct.dedup();
+            distinct
+        };
+        let classes = distinct(truth);
+        let clusters = distinct(labels);
+
+        let mut counts = vec![vec![0_usize; clusters.len()]; classes.len()];
+        for (class, label) in truth.iter().zip(labels) {
+            let row = classes.binary_search(class).unwrap();
+            let column = clusters.binary_search(label).unwrap();
+            counts[row][column] += 1;
+        }
+
+        Self {
+            classes,
+            clusters,
+            counts,
+        }
+    }
+
+    fn total(&self) -> usize {
+        self.counts.iter().flatten().sum()
+    }
+
+    fn row_sums(&self) -> Vec<usize> {
+        self.counts.iter().map(|row| row.iter().sum()).collect()
+    }
+
+    fn column_sums(&self) -> Vec<usize> {
+        (0..self.clusters.len())
+            .map(|column| self.counts.iter().map(|row| row[column]).sum())
+            .collect()
+    }
+}
+
+/// Agreement between a clustering and the true classes of its points, for
+/// when those are known, such as for generated points.
+///
+/// Every score is 1 when the clusters are exactly the classes, whatever
+/// their labels, and none depends on how either is numbered.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Agreement {
+    pub contingency: Contingency,
+    /// Adjusted Rand Index, from Hubert and Arabie's "Comparing Partitions"
+    /// (1985): the fraction of pairs of points both partitions agree on
+    /// grouping or separating, corrected for chance. Near 0 for a random
+    /// clustering, and negative for one worse than random.
+    pub adjusted_rand_index: f64,
+    /// Mutual information between classes and clusters over the arithmetic
+    /// mean of their entropies, from 0 to 1. With this normalization it
+    /// equals the V-measure.
+    pub normalized_mutual_information: f64,
+    /// Homogeneity, from Rosenberg and Hirschberg's "V-Measure: A Conditional
+    /// Entropy-Based External Cluster Evaluation Measure" (2007): 1 when every
+    /// cluster holds points of a single class.
+    pub homogeneity: f64,
+    /// Completeness, from the same: 1 when every class lies within a single
+    /// cluster.
+    pub completeness: f64,
+    /// Harmonic mean of homogeneity and completeness.
+    pub v_measure: f64,
+    /// Fraction of points belonging to the most common class of their cluster.
+    pub purity: f64,
+}
+
+/// Compare the cluster label of every point with its true label.
+///
+/// ```
+/// use kmeans_rs::evaluation::agreement;
+///
+/// // the same partition under other labels
+/// let perfect = agreement(&[0, 0, 1, 1, 2, 2], &[1, 1, 2, 2, 0, 0]);
+/// assert_eq!(1.0, perfect.adjusted_rand_index);
+/// assert_eq!(1.0, perfect.v_measure);
+///
+/// // splitting a class keeps clusters homogeneous but not complete
+/// let split = agreement(&[0, 0, 1, 1], &[0, 1, 2, 2]);
+/// assert_eq!(1.0, split.homogeneity);
+/// assert!(split.completeness < 1.0);
+/// assert_eq!(vec![vec![1, 1, 0], vec![0, 0, 2]], split.contingency.counts);
+/// ```
+///
+/// # Panics
+///
+/// If `truth` and `labels` differ in length.
+pub fn agreement(truth: &[usize], labels: &[usize]) -> Agreement {
+    let contingency = Contingency::new(truth, labels);
+    let rows = contingency.row_sums();
+    let columns = contingency.column_sums();
+    let n = contingency.total();
+
+    // pairs of points grouped together by both partitions, by the classes,
+    // and by the clusters
+    let pairs = |count: &usize| (count * count.saturating_sub(1) / 2) as f64;
+    let together = contingency.counts.iter().flatten().map(pairs).sum::<f64>();
+    let in_classes = rows.iter().map(pairs).sum::<f64>();
+    let in_clusters = columns.iter().map(pairs).sum::<f64>();
+    let expected = match n > 1 {
+        true => in_classes * in_clusters / pairs(&n),
+        false => 0_f64,
+    };
+    let maximum = (in_classes + in_clusters) / 2_f64;
+    let adjusted_rand_index = match maximum > expected {
+        true => (together - expected) / (maximum - expected),
+        false => 1_f64,
+    };
+
+    // entropies in nats, and the mutual information of classes and clusters
+    let entropy = |sums: &[usize]| {
+        sums.iter()
+            .filter(|sum| **sum > 0)
+            .map(|sum| {
+                let p = *sum as f64 / n as f64;
+                -p * p.ln()
+            })
+            .sum::<f64>()
+    };
+    let class_entropy = entropy(&rows);
+    let cluster_entropy = entropy(&columns);
+    let mut mutual_information = 0_f64;
+    for (row, row_sum) in contingency.counts.iter().zip(&rows) {
+        for (count, column_sum) in row.iter().zip(&columns) {
+            if *count > 0 {
+                let joint = *count as f64 / n as f64;
+                let independent = (*row_sum * *column_sum) as f64 / (n * n) as f64;
+                mutual_information += joint * (joint / independent).ln();
+            }
+        }
+    }
+    // rounding can leave the information a hair above either entropy
+    let mutual_information = mutual_information
+        .max(0_f64)
+        .min(class_entropy.min(cluster_entropy));
+
+    let homogeneity = match class_entropy > 0_f64 {
+        true => mutual_information / class_entropy,
+        false => 1_f64,
+    };
+    let completeness = match cluster_entropy > 0_f64 {
+        true => mutual_information / cluster_entropy,
+        false => 1_f64,
+    };
+    let v_measure = match homogeneity + completeness > 0_f64 {
+        true => 2_f64 * homogeneity * completeness / (homogeneity + completeness),
+        false => 0_f64,
+    };
+    let normalized_mutual_information = match class_entropy + cluster_entropy > 0_f64 {
+        true => 2_f64 * mutual_information / (class_entropy + cluster_entropy),
+        false => 1_f64,
+    };
+
+    let purity = match n {
+        0 => 1_f64,
+        n => {
+            let majorities: usize = (0..columns.len())
+                .map(|column| {
+                    contingency
+                        .counts
+                        .iter()
+                        .map(|row| row[column])
+                        .max()
+                        .unwrap()
+                })
+                .sum();
+            majorities as f64 / n as f64
+        }
+    };
+
+    Agreement {
+        contingency,
+        adjusted_rand_index,
+        normalized_mutual_information,
+        homogeneity,
+        completeness,
+        v_measure,
+        purity,
+    }
+}
+
+// distance between two points for scoring purposes, under which squared
+// Euclidean distance becomes plain Euclidean distance
+fn dissimilarity(metric: &Metric, a: &Point, b: &Point) -> f64 {
+    match metric {
+        Metric::SquaredEuclidean => metric.distance(a, b).sqrt(),
+        _ => metric.distance(a, b),
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,344 @@
+use super::*;
+use crate::kmeans::{execute, Init, KMeans};
+use crate::point::{generate_clustered_points, generate_labelled_points};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+fn test_points() -> (Vec<Point>, Vec<usize>) {
+    let points = vec![
+        Point::new(vec![0_f64, 0_f64]),
+        Point::new(vec![0_f64, 4_f64]),
+        Point::new(vec![3_f64, 0_f64]),
+        Point::new(vec![20_f64, 0_f64]),
+        Point::new(vec![20_f64, 3_f64]),
+    ];
+
+    (points, vec![0, 0, 0, 1, 1])
+}
+
+#[test]
+fn test_silhouette() {
+    let (points, labels) = test_points();
+    let silhouette = silhouette(&Metric::SquaredEuclidean, &points, &labels);
+
+    // the first point is 4 and 3 away from its cluster mates, and on average
+    // 20.1 from the other cluster
+    let b = (20_f64 + 409_f64.sqrt()) / 2_f64;
+    let expected = (b - 3.5) / b;
+    assert!((silhouette.points[0].unwrap() - expected).abs() < 1e-12);
+
+    let mean = |values: &[Option<f64>]| {
+        values.iter().map(|v| v.unwrap()).sum::<f64>() / values.len() as f64
+    };
+    assert!((silhouette.clusters[0].unwrap() - mean(&silhouette.points[..3])).abs() < 1e-12);
+    assert!((silhouette.clusters[1].unwrap() - mean(&silhouette.points[3..])).abs() < 1e-12);
+    assert!((silhouette.score - mean(&silhouette.points)).abs() < 1e-12);
+    assert!(!silhouette.sampled);
+}
+
+#[test]
+fn test_silhouette_uses_metric() {
+    // under manhattan distance the first point is 4 and 3 away from its
+    // cluster mates, and on average 21.5 from the other cluster
+    let (points, labels) = test_points();
+    let silhouette = silhouette(&Metric::Manhattan, &points, &labels);
+    let expected = (21.5 - 3.5) / 21.5;
+    assert!((silhouette.points[0].unwrap() - expected).abs() < 1e-12);
+}
+
+#[test]
+fn test_silhouette_edge_cases() {
+    let (points, _) = test_points();
+
+    // a single cluster has nothing to compare against
+    let single = silhouette(&Metric::SquaredEuclidean, &points, &[0; 5]);
+    assert!(single.points.iter().all(|s| *s == Some(0_f64)));
+    assert_eq!(0_f64, single.score);
+
+    // a point alone in its cluster scores 0, and an empty cluster has no mean
+    let lonely = silhouette(&Metric::SquaredEuclidean, &points, &[0, 0, 0, 2, 3]);
+    assert_eq!(Some(0_f64), lonely.points[3]);
+    assert_eq!(None, lonely.clusters[1]);
+
+    // a point closer to another cluster than its own scores below 0
+    let misplaced = silhouette(&Metric::SquaredEuclidean, &points, &[0, 0, 1, 1, 1]);
+    assert!(misplaced.points[2].unwrap() < 0_f64);
+}
+
+#[test]
+fn test_silhouette_sampled() {
+    let mut r = StdRng::seed_from_u64(71);
+    let bounds = (
+        &Point::new(vec![0_f64, 0_f64]),
+        &Point::new(vec![1000_f64, 1000_f64]),
+    );
+    let points = generate_clustered_points(&mut r, bounds, 4, 2000);
+    let labels: Vec<usize> = points
+        .iter()
+        .map(|p| (p.coords[0] > 500_f64) as usize + 2 * (p.coords[1] > 500_f64) as usize)
+        .collect();
+    let metric = Metric::SquaredEuclidean;
+
+    let exact = silhouette(&metric, &points, &labels);
+    let sampled = silhouette_sampled(&metric, &points, &labels, 500, &mut r);
+    assert!(sampled.sampled);
+    assert_eq!(500, sampled.points.iter().filter(|s| s.is_some()).count());
+    assert!((sampled.score - exact.score).abs() < 0.05);
+
+    // sampled points score exactly as they would without sampling
+    for (s, e) in sampled.points.iter().zip(&exact.points) {
+        if let Some(s) = s {
+            assert!((s - e.unwrap()).abs() < 1e-12);
+        }
+    }
+
+    // a sample covering every point is the exact computation
+    let full = silhouette_sampled(&metric, &points, &labels, 2000, &mut r);
+    assert_eq!(exact, full);
+}
+
+// two tight pairs of points, 10 apart
+fn two_pairs() -> (Vec<Point>, Clustering) {
+    let points = vec![
+        Point::new(vec![0_f64, 0_f64]),
+        Point::new(vec![0_f64, 2_f64]),
+        Point::new(vec![10_f64, 0_f64]),
+        Point::new(vec![10_f64, 2_f64]),
+    ];
+    let clustering = Clustering {
+        centroids: vec![
+            Point::new(vec![0_f64, 1_f64]),
+            Point::new(vec![10_f64, 1_f64]),
+        ],
+        labels: vec![0, 0, 1, 1],
+        inertia: 4_f64,
+        medoids: None,
+        memberships: None,
+        split_tree: None,
+    };
+
+    (points, clustering)
+}
+
+#[test]
+fn test_davies_bouldin() {
+    // each cluster spreads 1 around its centroid, 10 from the other
+    let (points, clustering) = two_pairs();
+    let index = davies_bouldin(&Metric::SquaredEuclidean, &points, &clustering).unwrap();
+    assert!((index - 0.2).abs() < 1e-12);
+
+    // under manhattan distance the centroids are just as far apart
+    let index = davies_bouldin(&Metric::Manhattan, &points, &clustering).unwrap();
+    assert!((index - 0.2).abs() < 1e-12);
+}
+
+#[test]
+fn test_davies_bouldin_coincident_centroids() {
+    // a third cluster centred exactly on the second is only compared with the
+    // first, 10 away
+    let (mut points, mut clustering) = two_pairs();
+    points.push(Point::new(vec![9_f64, 1_f64]));
+    points.push(Point::new(vec![11_f64, 1_f64]));
+    clustering.centroids.push(Point::new(vec![10_f64, 1_f64]));
+    clustering.labels.extend([2, 2].iter());
+    let index = davies_bouldin(&Metric::SquaredEuclidean, &points, &clustering).unwrap();
+    assert!((index - 0.2).abs() < 1e-12);
+
+    // clusters that all share a centroid have no separation at all
+    let (points, mut clustering) = two_pairs();
+    clustering.centroids[1] = clustering.centroids[0].clone();
+    assert_eq!(
+        None,
+        davies_bouldin(&Metric::SquaredEuclidean, &points, &clustering)
+    );
+}
+
+#[test]
+fn test_calinski_harabasz() {
+    // dispersion between clusters is 4 × 25, within them 4 × 1
+    let (points, clustering) = two_pairs();
+    let index = calinski_harabasz(&points, &clustering).unwrap();
+    assert!((index - 50_f64).abs() < 1e-12);
+}
+
+#[test]
+fn test_dunn() {
+    // the nearest points of different clusters are 10 apart, and the widest
+    // cluster 2 across
+    let (points, clustering) = two_pairs();
+    let index = dunn(&Metric::SquaredEuclidean, &points, &clustering.labels).unwrap();
+    assert!((index - 5_f64).abs() < 1e-12);
+}
+
+#[test]
+fn test_dunn_sampled() {
+    let (points, clustering) = two_pairs();
+    let mut r = StdRng::seed_from_u64(3);
+    let exact = dunn(&Metric::SquaredEuclidean, &points, &clustering.labels);
+    let labels = &clustering.labels;
+    let whole = dunn_sampled(&Metric::SquaredEuclidean, &points, labels, 4, &mut r);
+    assert_eq!(exact, whole);
+
+    // a sample never finds points closer across clusters, or farther apart
+    // within one, than all of them
+    let bounds = (
+        &Point::new(vec![0_f64, 0_f64]),
+        &Point::new(vec![1000_f64, 1000_f64]),
+    );
+    let (points, labels) = generate_labelled_points(&mut r, bounds, 3, 600);
+    let exact = dunn(&Metric::SquaredEuclidean, &points, &labels).unwrap();
+    for _ in 0..5 {
+        let sampled = dunn_sampled(&Metric::SquaredEuclidean, &points, &labels, 100, &mut r);
+        assert!(sampled.unwrap() >= exact);
+    }
+}
+
+#[test]
+fn test_indices_prefer_the_true_clustering() {
+    let (points, good) = two_pairs();
+    let bad = Clustering {
+        centroids: vec![
+            Point::new(vec![5_f64, 0_f64]),
+            Point::new(vec![5_f64, 2_f64]),
+        ],
+        labels: vec![0, 1, 0, 1],
+        inertia: 100_f64,
+        ..good.clone()
+    };
+    let metric = Metric::SquaredEuclidean;
+
+    assert!(davies_bouldin(&metric, &points, &good) < davies_bouldin(&metric, &points, &bad));
+    assert!(calinski_harabasz(&points, &good) > calinski_harabasz(&points, &bad));
+    assert!(dunn(&metric, &points, &good.labels) > dunn(&metric, &points, &bad.labels));
+}
+
+#[test]
+fn test_indices_need_two_clusters() {
+    let (points, clustering) = two_pairs();
+    let single = Clustering {
+        labels: vec![0; 4],
+        ..clustering.clone()
+    };
+    let metric = Metric::SquaredEuclidean;
+
+    // the second cluster is empty
+    assert_eq!(None, davies_bouldin(&metric, &points, &single));
+    assert_eq!(None, calinski_harabasz(&points, &single));
+    assert_eq!(None, dunn(&metric, &points, &single.labels));
+
+    // every point alone in its cluster leaves no dispersion within them
+    let singletons = Clustering {
+        centroids: points.clone(),
+        labels: vec![0, 1, 2, 3],
+        ..clustering
+    };
+    assert_eq!(None, calinski_harabasz(&points, &singletons));
+    assert_eq!(None, dunn(&metric, &points, &singletons.labels));
+    assert_eq!(Some(0_f64), davies_bouldin(&metric, &points, &singletons));
+}
+
+#[test]
+fn test_index_from_str() {
+    for index in Index::ALL {
+        assert_eq!(Ok(index), index.to_string().parse());
+    }
+    assert!("davies_bouldin".parse::<Index>().is_err());
+}
+
+#[test]
+fn test_index_score() {
+    let (points, clustering) = two_pairs();
+    let metric = Metric::SquaredEuclidean;
+    let r = &mut StdRng::seed_from_u64(1);
+
+    let mut score =
+        |index: Index, clustering: &Clustering| index.score(&metric, &points, clustering, 100, r);
+    assert_eq!(Some(5_f64), score(Index::Dunn, &clustering));
+    assert_eq!(
+        Some(silhouette(&metric, &points, &clustering.labels).score),
+        score(Index::Silhouette, &clustering)
+    );
+
+    // a single cluster has no silhouette score either
+    let single = Clustering {
+        labels: vec![0; 4],
+        ..clustering.clone()
+    };
+    assert_eq!(None, score(Index::Silhouette, &single));
+    assert!(!Index::DaviesBouldin.higher_is_better());
+}
+
+#[test]
+fn test_contingency() {
+    let contingency = Contingency::new(&[3, 3, 7, 7, 7], &[1, 4, 4, 4, 1]);
+    assert_eq!(vec![3, 7], contingency.classes);
+    assert_eq!(vec![1, 4], contingency.clusters);
+    assert_eq!(vec![vec![1, 1], vec![1, 2]], contingency.counts);
+    assert_eq!(5, contingency.total());
+}
+
+#[test]
+fn test_agreement() {
+    // one class split across two clusters
+    let split = agreement(&[0, 0, 1, 1], &[0, 0, 1, 2]);
+    assert!((split.adjusted_rand_index - 4_f64 / 7_f64).abs() < 1e-12);
+    assert!((split.homogeneity - 1_f64).abs() < 1e-12);
+    assert!((split.completeness - 2_f64 / 3_f64).abs() < 1e-12);
+    assert!((split.v_measure - 0.8).abs() < 1e-12);
+    assert!((split.normalized_mutual_information - 0.8).abs() < 1e-12);
+    assert_eq!(1_f64, split.purity);
+
+    // swapping the partitions swaps homogeneity and completeness
+    let merged = agreement(&[0, 0, 1, 2], &[0, 0, 1, 1]);
+    assert!((merged.adjusted_rand_index - split.adjusted_rand_index).abs() < 1e-12);
+    assert!((merged.homogeneity - split.completeness).abs() < 1e-12);
+    assert!((merged.completeness - split.homogeneity).abs() < 1e-12);
+    assert_eq!(0.75, merged.purity);
+
+    // every pair the classes group, the clusters separate
+    let crossed = agreement(&[0, 0, 1, 1], &[0, 1, 0, 1]);
+    assert!((crossed.adjusted_rand_index + 0.5).abs() < 1e-12);
+    assert!(crossed.homogeneity.abs() < 1e-12);
+    assert!(crossed.v_measure.abs() < 1e-12);
+}
+
+#[test]
+fn test_agreement_edge_cases() {
+    let single = agreement(&[2, 2, 2], &[5, 5, 5]);
+    assert_eq!(1_f64, single.adjusted_rand_index);
+    assert_eq!(1_f64, single.normalized_mutual_information);
+    assert_eq!(1_f64, single.v_measure);
+
+    // singletons against a single class share no information
+    let singletons = agreement(&[0, 0, 0], &[0, 1, 2]);
+    assert_eq!(0_f64, singletons.adjusted_rand_index);
+    assert_eq!(1_f64, singletons.homogeneity);
+    assert_eq!(0_f64, singletons.completeness);
+    assert_eq!(0_f64, singletons.normalized_mutual_information);
+}
+
+#[test]
+fn test_agreement_with_generated_labels() {
+    let bounds = (
+        &Point::new(vec![0_f64, 0_f64]),
+        &Point::new(vec![1000_f64, 1000_f64]),
+    );
+    let mut r = StdRng::seed_from_u64(23);
+    let (points, truth) = generate_labelled_points(&mut r, bounds, 4, 400);
+    let params = KMeans::new(4).init(Init::KMeansPlusPlus).n_init(4);
+    let labels = execute(&params, &points, &mut r).unwrap().clustering.labels;
+
+    let found = agreement(&truth, &labels);
+    let random: Vec<usize> = (0..points.len()).map(|_| r.gen_range(0..4)).collect();
+    let chance = agreement(&truth, &random);
+    assert!(found.adjusted_rand_index > 0.5);
+    assert!(found.adjusted_rand_index > chance.adjusted_rand_index + 0.4);
+    assert!(chance.adjusted_rand_index.abs() < 0.05);
+    assert!(found.v_measure > chance.v_measure);
+}
+
+#[test]
+#[should_panic]
+fn test_agreement_length_mismatch() {
+    agreement(&[0, 1], &[0]);
+}
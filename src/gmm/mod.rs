@@ -0,0 +1,475 @@
+use crate::kmeans::Clustering;
+use crate::point::Point;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Shape of the covariance matrix fitted to each mixture component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Covariance {
+    /// An unrestricted covariance matrix per component, for ellipses of any
+    /// orientation.
+    #[serde(rename = "full")]
+    Full,
+    /// A variance per dimension, for ellipses aligned with the axes.
+    #[serde(rename = "diag")]
+    Diagonal,
+    /// A single variance per component, for circles.
+    #[serde(rename = "spherical")]
+    Spherical,
+}
+
+impl Display for Covariance {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Covariance::Full => write!(f, "full"),
+            Covariance::Diagonal => write!(f, "diag"),
+            Covariance::Spherical => write!(f, "spherical"),
+        }
+    }
+}
+
+// convert raw String input of the form "diag" into Covariance
+impl FromStr for Covariance {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(Covariance::Full),
+            "diag" => Ok(Covariance::Diagonal),
+            "spherical" => Ok(Covariance::Spherical),
+            _ => Err(format!(
+                "unknown covariance type '{}', expected one of: full, diag, spherical",
+                s
+            )),
+        }
+    }
+}
+
+/// Error raised when a Gaussian mixture cannot be fitted.
+#[derive(Debug)]
+pub struct GaussianMixtureError {
+    err_msg: String,
+}
+
+impl GaussianMixtureError {
+    fn new(msg: &str) -> Box<Self> {
+        Box::new(Self {
+            err_msg: msg.to_string(),
+        })
+    }
+}
+
+impl Display for GaussianMixtureError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.err_msg)
+    }
+}
+
+impl Error for GaussianMixtureError {
+    fn description(&self) -> &str {
+        &self.err_msg
+    }
+}
+
+/// Configuration for fitting a Gaussian mixture model by expectation
+/// maximization, starting from a k-means clustering.
+///
+/// ```
+/// use kmeans_rs::{Covariance, GaussianMixture, Init, KMeans, Point};
+/// use kmeans_rs::kmeans::execute;
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+///
+/// let points = vec![
+///     Point::new(vec![1.0, 1.0]),
+///     Point::new(vec![1.5, 2.0]),
+///     Point::new(vec![2.0, 1.2]),
+///     Point::new(vec![8.0, 8.0]),
+///     Point::new(vec![9.0, 8.5]),
+///     Point::new(vec![8.4, 9.3]),
+/// ];
+///
+/// let params = KMeans::new(2).init(Init::KMeansPlusPlus);
+/// let kmeans = execute(&params, &points, &mut StdRng::seed_from_u64(42)).unwrap();
+/// let model = GaussianMixture::new(Covariance::Full)
+///     .fit(&points, &kmeans.clustering)
+///     .unwrap();
+///
+/// assert_eq!(2, model.means.len());
+/// assert_eq!(model.labels[0], model.labels[1]);
+/// assert_ne!(model.labels[0], model.labels[3]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct GaussianMixture {
+    covariance: Covariance,
+    max_iterations: usize,
+    tolerance: f64,
+    regularization: f64,
+}
+
+impl GaussianMixture {
+    /// Start configuring a fit with the given covariance shape.
+    pub fn new(covariance: Covariance) -> Self {
+        Self {
+            covariance,
+            max_iterations: 100,
+            tolerance: 1e-3,
+            regularization: 1e-6,
+        }
+    }
+
+    /// Maximum number of EM iterations to perform without converging (default 100).
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Change in log-likelihood per point below which a fit has converged
+    /// (default 0.001).
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Variance added to the diagonal of every covariance matrix, keeping it
+    /// invertible when a component collapses onto few points (default 1e-6).
+    pub fn regularization(mut self, regularization: f64) -> Self {
+        self.regularization = regularization;
+        self
+    }
+
+    /// Fit one component per cluster of `init`, a clustering of `points`.
+    /// Components start at the clustering's centroids, with the covariance of
+    /// each cluster's members around them and weights proportional to the
+    /// cluster sizes.
+    pub fn fit(
+        &self,
+        points: &[Point],
+        init: &Clustering,
+    ) -> Result<GaussianMixtureModel, Box<GaussianMixtureError>> {
+        self.validate(points, init)?;
+
+        // the clustering stands in for the first E-step, every point belonging
+        // to its cluster alone
+        let k = init.centroids.len();
+        let assigned: Vec<Vec<f64>> = init
+            .labels
+            .iter()
+            .map(|label| (0..k).map(|j| (j == *label) as usize as f64).collect())
+            .collect();
+        let mut components = self.maximization(points, &assigned, Some(&init.centroids))?;
+        let (mut responsibilities, mut log_likelihood) = expectation(points, &components);
+
+        let mut history = vec![log_likelihood];
+        let mut converged = false;
+        let mut iterations = 0;
+        for iter in 1..=self.max_iterations {
+            components = self.maximization(points, &responsibilities, None)?;
+            let (next_responsibilities, next_log_likelihood) = expectation(points, &components);
+            let change = (next_log_likelihood - log_likelihood) / points.len() as f64;
+
+            responsibilities = next_responsibilities;
+            log_likelihood = next_log_likelihood;
+            history.push(log_likelihood);
+            iterations = iter;
+
+            if change.abs() < self.tolerance {
+                converged = true;
+                break;
+            }
+        }
+
+        // the most probable component, breaking ties toward the lowest index
+        let labels = responsibilities
+            .iter()
+            .map(|r| {
+                r.iter()
+                    .enumerate()
+                    .fold((0, f64::MIN), |acc, (ndx, p)| match *p > acc.1 {
+                        true => (ndx, *p),
+                        false => acc,
+                    })
+                    .0
+            })
+            .collect();
+
+        let parameters = self.free_parameters(k, points[0].dim()) as f64;
+        Ok(GaussianMixtureModel {
+            covariance: self.covariance,
+            weights: components.iter().map(|c| c.weight).collect(),
+            means: components.iter().map(|c| c.mean.clone()).collect(),
+            covariances: components.into_iter().map(|c| c.covariance).collect(),
+            responsibilities,
+            labels,
+            log_likelihood,
+            log_likelihood_history: history,
+            aic: 2_f64 * parameters - 2_f64 * log_likelihood,
+            bic: parameters * (points.len() as f64).ln() - 2_f64 * log_likelihood,
+            converged,
+            iterations,
+        })
+    }
+
+    fn validate(
+        &self,
+        points: &[Point],
+        init: &Clustering,
+    ) -> Result<(), Box<GaussianMixtureError>> {
+        if self.max_iterations < 1 {
+            return Err(GaussianMixtureError::new(
+                "kmeans-rs: no point in performing less than 1 gmm iteration",
+            ));
+        }
+
+        if self.tolerance.is_nan() || self.tolerance < 0_f64 {
+            return Err(GaussianMixtureError::new(
+                "kmeans-rs: gmm tolerance cannot be negative",
+            ));
+        }
+
+        if self.regularization.is_nan() || self.regularization < 0_f64 {
+            return Err(GaussianMixtureError::new(
+                "kmeans-rs: gmm regularization cannot be negative",
+            ));
+        }
+
+        if init.centroids.is_empty() || points.len() != init.labels.len() {
+            return Err(GaussianMixtureError::new(
+                "kmeans-rs: gmm must start from a clustering of the same points",
+            ));
+        }
+
+        let dim = init.centroids[0].dim();
+        if points.iter().chain(&init.centroids).any(|p| p.dim() != dim) {
+            return Err(GaussianMixtureError::new(
+                "kmeans-rs: gmm points and centroids must all have the same dimension",
+            ));
+        }
+
+        Ok(())
+    }
+
+    // number of values the model estimates: weights summing to 1, means and
+    // the covariances' distinct entries
+    fn free_parameters(&self, k: usize, dim: usize) -> usize {
+        let covariance = match self.covariance {
+            Covariance::Full => dim * (dim + 1) / 2,
+            Covariance::Diagonal => dim,
+            Covariance::Spherical => 1,
+        };
+
+        k - 1 + k * dim + k * covariance
+    }
+
+    // M-step: components maximizing the expected log-likelihood given each
+    // point's responsibilities, centered on means when given. every sum runs
+    // over the points in input order, independent of the number of threads
+    fn maximization(
+        &self,
+        points: &[Point],
+        responsibilities: &[Vec<f64>],
+        means: Option<&[Point]>,
+    ) -> Result<Vec<Component>, Box<GaussianMixtureError>> {
+        let k = responsibilities[0].len();
+        let dim = points[0].dim();
+
+        let components: Vec<Option<Component>> = (0..k)
+            .into_par_iter()
+            .map(|j| {
+                // a component nobody belongs to keeps a tiny weight rather than
+                // dividing by zero
+                let total = responsibilities.iter().fold(0_f64, |acc, r| acc + r[j])
+                    + 10_f64 * f64::EPSILON;
+                let mean = match means {
+                    Some(means) => means[j].clone(),
+                    None => {
+                        let mut sum = Point::zero(dim);
+                        for (point, r) in points.iter().zip(responsibilities) {
+                            sum.coords
+                                .iter_mut()
+                                .zip(&point.coords)
+                                .for_each(|(s, c)| *s += r[j] * c);
+                        }
+                        Point::new(sum.coords.iter().map(|s| s / total).collect())
+                    }
+                };
+
+                let mut scatter = vec![vec![0_f64; dim]; dim];
+                for (point, r) in points.iter().zip(responsibilities) {
+                    let offset: Vec<f64> = point
+                        .coords
+                        .iter()
+                        .zip(&mean.coords)
+                        .map(|(c, m)| c - m)
+                        .collect();
+                    for a in 0..dim {
+                        for b in 0..=a {
+                            scatter[a][b] += r[j] * offset[a] * offset[b];
+                        }
+                    }
+                }
+
+                let mut covariance = vec![vec![0_f64; dim]; dim];
+                let average_variance = (0..dim).map(|d| scatter[d][d]).sum::<f64>() / dim as f64;
+                for a in 0..dim {
+                    for b in 0..=a {
+                        let value = match self.covariance {
+                            Covariance::Full => scatter[a][b] / total,
+                            Covariance::Diagonal if a == b => scatter[a][b] / total,
+                            Covariance::Spherical if a == b => average_variance / total,
+                            _ => 0_f64,
+                        };
+                        covariance[a][b] = value;
+                        covariance[b][a] = value;
+                    }
+                    covariance[a][a] += self.regularization;
+                }
+
+                Component::new(total / points.len() as f64, mean, covariance)
+            })
+            .collect();
+
+        components
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| {
+                GaussianMixtureError::new(
+                    "kmeans-rs: a gmm covariance is not positive definite, try a larger regularization",
+                )
+            })
+    }
+}
+
+/// Gaussian mixture fitted by [`GaussianMixture::fit`], indexed by component.
+#[derive(Debug, Clone, Serialize)]
+pub struct GaussianMixtureModel {
+    pub covariance: Covariance,
+    /// Mixing weight of each component, summing to 1.
+    pub weights: Vec<f64>,
+    pub means: Vec<Point>,
+    /// Covariance matrix of each component, as rows. Diagonal and spherical
+    /// covariances are written out in full, with zeros off the diagonal.
+    pub covariances: Vec<Vec<Vec<f64>>>,
+    /// Probability of each input point belonging to each component, indexed by
+    /// point and then component.
+    pub responsibilities: Vec<Vec<f64>>,
+    /// Most probable component of each input point.
+    pub labels: Vec<usize>,
+    /// Log-likelihood of the input points under the final model.
+    pub log_likelihood: f64,
+    /// Log-likelihood after initialization (first) and after each iteration.
+    pub log_likelihood_history: Vec<f64>,
+    /// Akaike information criterion, lower is better.
+    pub aic: f64,
+    /// Bayesian information criterion, lower is better.
+    pub bic: f64,
+    pub converged: bool,
+    /// Number of EM iterations performed after initialization.
+    pub iterations: usize,
+}
+
+// a weighted Gaussian, along with the Cholesky factor of its covariance
+struct Component {
+    weight: f64,
+    mean: Point,
+    covariance: Vec<Vec<f64>>,
+    cholesky: Vec<Vec<f64>>,
+    log_det: f64,
+}
+
+impl Component {
+    // None unless covariance is positive definite
+    fn new(weight: f64, mean: Point, covariance: Vec<Vec<f64>>) -> Option<Self> {
+        let cholesky = cholesky(&covariance)?;
+        let log_det = 2_f64
+            * (0..cholesky.len())
+                .map(|d| cholesky[d][d].ln())
+                .sum::<f64>();
+
+        Some(Self {
+            weight,
+            mean,
+            covariance,
+            cholesky,
+            log_det,
+        })
+    }
+
+    // log of the weighted probability density at point
+    fn log_density(&self, point: &Point) -> f64 {
+        let dim = point.dim();
+
+        // the squared Mahalanobis distance is the squared norm of y solving
+        // L y = point - mean, by forward substitution
+        let mut y = vec![0_f64; dim];
+        for a in 0..dim {
+            let partial = (0..a).fold(0_f64, |acc, b| acc + self.cholesky[a][b] * y[b]);
+            y[a] = (point.coords[a] - self.mean.coords[a] - partial) / self.cholesky[a][a];
+        }
+        let mahalanobis = y.iter().map(|v| v * v).sum::<f64>();
+
+        self.weight.ln() - 0.5 * (dim as f64 * (2_f64 * PI).ln() + self.log_det + mahalanobis)
+    }
+}
+
+// E-step: probability of each point belonging to each component, and the
+// log-likelihood of all points. densities are combined in log space, so far
+// away points do not underflow
+fn expectation(points: &[Point], components: &[Component]) -> (Vec<Vec<f64>>, f64) {
+    let (responsibilities, log_likelihoods): (Vec<Vec<f64>>, Vec<f64>) = points
+        .par_iter()
+        .map(|point| {
+            let log_densities: Vec<f64> = components.iter().map(|c| c.log_density(point)).collect();
+            let max = log_densities
+                .iter()
+                .fold(f64::MIN, |acc, l| f64::max(acc, *l));
+            let log_total = max
+                + log_densities
+                    .iter()
+                    .map(|l| (l - max).exp())
+                    .sum::<f64>()
+                    .ln();
+
+            let responsibilities = log_densities
+                .iter()
+                .map(|l| (l - log_total).exp())
+                .collect();
+            (responsibilities, log_total)
+        })
+        .unzip();
+
+    // summed in input order, independent of the number of threads
+    let log_likelihood = log_likelihoods.iter().fold(0_f64, |acc, l| acc + l);
+    (responsibilities, log_likelihood)
+}
+
+// lower triangular L with L Lᵀ = matrix, or None unless matrix is symmetric
+// positive definite
+fn cholesky(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let dim = matrix.len();
+    let mut lower = vec![vec![0_f64; dim]; dim];
+    for a in 0..dim {
+        for b in 0..=a {
+            let partial = (0..b).fold(0_f64, |acc, c| acc + lower[a][c] * lower[b][c]);
+            match a == b {
+                true => {
+                    let pivot = matrix[a][a] - partial;
+                    if pivot.is_nan() || pivot <= 0_f64 {
+                        return None;
+                    }
+                    lower[a][a] = pivot.sqrt();
+                }
+                false => lower[a][b] = (matrix[a][b] - partial) / lower[b][b],
+            }
+        }
+    }
+
+    Some(lower)
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,190 @@
+use super::*;
+use crate::kmeans::{execute, Init, KMeans};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// a standard normal sample, drawn with the Box-Muller transform
+fn normal<R: Rng + ?Sized>(r: &mut R) -> f64 {
+    let u1: f64 = 1_f64 - r.gen::<f64>();
+    let u2: f64 = r.gen();
+    (-2_f64 * u1.ln()).sqrt() * (2_f64 * PI * u2).cos()
+}
+
+// two elongated blobs: one stretched along x, the other tilted along y = x
+fn anisotropic_blobs<R: Rng + ?Sized>(r: &mut R) -> Vec<Point> {
+    let mut points = vec![];
+    for _ in 0..300 {
+        let (a, b) = (normal(r), normal(r));
+        points.push(Point::new(vec![20_f64 + 6_f64 * a, 20_f64 + b]));
+    }
+    for _ in 0..300 {
+        let (a, b) = (normal(r), normal(r));
+        points.push(Point::new(vec![
+            70_f64 + 4_f64 * a + b,
+            70_f64 + 4_f64 * a - b,
+        ]));
+    }
+
+    points
+}
+
+fn kmeans_clustering(points: &[Point], k: usize) -> Clustering {
+    let params = KMeans::new(k).init(Init::KMeansPlusPlus);
+    execute(&params, points, &mut StdRng::seed_from_u64(4))
+        .unwrap()
+        .clustering
+}
+
+#[test]
+fn test_covariance_from_str() {
+    assert_eq!(Ok(Covariance::Full), "full".parse());
+    assert_eq!(Ok(Covariance::Diagonal), "diag".parse());
+    assert_eq!(Ok(Covariance::Spherical), "spherical".parse());
+    assert!("diagonal".parse::<Covariance>().is_err());
+    assert_eq!("diag", Covariance::Diagonal.to_string());
+}
+
+#[test]
+fn test_cholesky() {
+    let lower = cholesky(&[vec![4_f64, 2_f64], vec![2_f64, 5_f64]]).unwrap();
+    assert_eq!(vec![vec![2_f64, 0_f64], vec![1_f64, 2_f64]], lower);
+
+    // not positive definite
+    assert!(cholesky(&[vec![1_f64, 2_f64], vec![2_f64, 1_f64]]).is_none());
+}
+
+#[test]
+fn test_log_density() {
+    // a standard normal in 2 dimensions, evaluated at its mean and one unit away
+    let identity = vec![vec![1_f64, 0_f64], vec![0_f64, 1_f64]];
+    let component = Component::new(1_f64, Point::zero(2), identity).unwrap();
+    let peak = -(2_f64 * PI).ln();
+    let unit = Point::new(vec![1_f64, 0_f64]);
+    assert!((component.log_density(&Point::zero(2)) - peak).abs() < 1e-12);
+    assert!((component.log_density(&unit) - (peak - 0.5)).abs() < 1e-12);
+
+    // halving the weight lowers the log density by ln 2
+    let covariance = vec![vec![4_f64, 0_f64], vec![0_f64, 1_f64]];
+    let component = Component::new(0.5, Point::zero(2), covariance).unwrap();
+    let expected = 0.5_f64.ln() - (2_f64 * PI).ln() - 2_f64.ln() - 0.5;
+    let point = Point::new(vec![2_f64, 0_f64]);
+    assert!((component.log_density(&point) - expected).abs() < 1e-12);
+}
+
+#[test]
+fn test_gmm_recovers_anisotropic_blobs() {
+    let points = anisotropic_blobs(&mut StdRng::seed_from_u64(7));
+    let model = GaussianMixture::new(Covariance::Full)
+        .fit(&points, &kmeans_clustering(&points, 2))
+        .unwrap();
+    assert!(model.converged);
+
+    let first = model.labels[0];
+    assert!(model.labels[..300].iter().all(|label| *label == first));
+    assert!(model.labels[300..].iter().all(|label| *label != first));
+
+    let (x, tilted) = (&model.covariances[first], &model.covariances[1 - first]);
+    assert!((x[0][0] - 36_f64).abs() < 8_f64);
+    assert!((x[1][1] - 1_f64).abs() < 0.3);
+    assert!(x[0][1].abs() < 2_f64);
+    assert!((tilted[0][1] - 15_f64).abs() < 4_f64);
+    assert!((model.weights[first] - 0.5).abs() < 1e-6);
+    assert!((model.weights.iter().sum::<f64>() - 1_f64).abs() < 1e-9);
+}
+
+#[test]
+fn test_gmm_log_likelihood_never_decreases() {
+    let points = anisotropic_blobs(&mut StdRng::seed_from_u64(11));
+    let init = kmeans_clustering(&points, 3);
+    for covariance in [
+        Covariance::Full,
+        Covariance::Diagonal,
+        Covariance::Spherical,
+    ] {
+        let model = GaussianMixture::new(covariance)
+            .tolerance(0_f64)
+            .max_iterations(30)
+            .fit(&points, &init)
+            .unwrap();
+
+        assert_eq!(31, model.log_likelihood_history.len());
+        for (prev, next) in model
+            .log_likelihood_history
+            .iter()
+            .zip(&model.log_likelihood_history[1..])
+        {
+            assert!(*next >= prev - 1e-6 * prev.abs());
+        }
+        for responsibilities in &model.responsibilities {
+            assert!((responsibilities.iter().sum::<f64>() - 1_f64).abs() < 1e-9);
+        }
+    }
+}
+
+#[test]
+fn test_gmm_covariance_shapes() {
+    let points = anisotropic_blobs(&mut StdRng::seed_from_u64(13));
+    let init = kmeans_clustering(&points, 2);
+
+    let diagonal = GaussianMixture::new(Covariance::Diagonal)
+        .fit(&points, &init)
+        .unwrap();
+    for covariance in &diagonal.covariances {
+        assert_eq!(0_f64, covariance[0][1]);
+        assert_eq!(0_f64, covariance[1][0]);
+        assert!(covariance[0][0] != covariance[1][1]);
+    }
+
+    let spherical = GaussianMixture::new(Covariance::Spherical)
+        .fit(&points, &init)
+        .unwrap();
+    for covariance in &spherical.covariances {
+        assert_eq!(0_f64, covariance[0][1]);
+        assert_eq!(covariance[0][0], covariance[1][1]);
+    }
+}
+
+#[test]
+fn test_gmm_information_criteria() {
+    let gmm = GaussianMixture::new;
+    assert_eq!(11, gmm(Covariance::Full).free_parameters(2, 2));
+    assert_eq!(9, gmm(Covariance::Diagonal).free_parameters(2, 2));
+    assert_eq!(7, gmm(Covariance::Spherical).free_parameters(2, 2));
+
+    let points = anisotropic_blobs(&mut StdRng::seed_from_u64(17));
+    let model = gmm(Covariance::Full)
+        .fit(&points, &kmeans_clustering(&points, 2))
+        .unwrap();
+    let ln_n = (points.len() as f64).ln();
+    assert!((model.aic - (22_f64 - 2_f64 * model.log_likelihood)).abs() < 1e-6);
+    assert!((model.bic - (11_f64 * ln_n - 2_f64 * model.log_likelihood)).abs() < 1e-6);
+
+    // full covariances describe the tilted blob better than circles, by far
+    // more than the extra parameters cost
+    let spherical = gmm(Covariance::Spherical)
+        .fit(&points, &kmeans_clustering(&points, 2))
+        .unwrap();
+    assert!(model.bic < spherical.bic);
+}
+
+#[test]
+fn test_gmm_validation() {
+    let points = anisotropic_blobs(&mut StdRng::seed_from_u64(19));
+    let init = kmeans_clustering(&points, 2);
+    let fit = |gmm: GaussianMixture, points: &[Point]| gmm.fit(points, &init).is_err();
+
+    assert!(fit(
+        GaussianMixture::new(Covariance::Full).max_iterations(0),
+        &points
+    ));
+    assert!(fit(
+        GaussianMixture::new(Covariance::Full).tolerance(-1_f64),
+        &points
+    ));
+    assert!(fit(
+        GaussianMixture::new(Covariance::Full).regularization(f64::NAN),
+        &points
+    ));
+    assert!(fit(GaussianMixture::new(Covariance::Full), &points[1..]));
+    assert!(!fit(GaussianMixture::new(Covariance::Full), &points));
+}
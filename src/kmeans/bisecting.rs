@@ -0,0 +1,194 @@
+use super::{
+    calculate_next_centroid, complete_clustering, initial_centroids, regroup_points, Clustering,
+    Convergence, DistanceEvaluations, HistoryRecorder, KMeans, KMeansError, Progress, Run,
+    SplitNode, SplitTree, StopReason,
+};
+use crate::distance::{Distance, Metric};
+use crate::point::Point;
+use rand::Rng;
+use rayon::prelude::*;
+
+// bisecting k-means (Steinbach, Karypis and Kumar, 2000), which builds the
+// clusters top down. every point starts out in a single cluster, and the
+// cluster with the largest inertia is repeatedly split in two by 2-means on its
+// members alone, until there are k. each split is an iteration, so the history
+// shows the clusters multiplying one at a time, and the tree of splits lets the
+// result be cut back to any smaller k.
+//
+// clusters keep their labels across splits: the first half of a split keeps
+// its parent's label and the second takes the next one, so the labels after
+// any number of splits are the final labels cut back to that many clusters
+pub(super) fn execute_once<R: Rng + ?Sized>(
+    params: &KMeans,
+    points: &[Point],
+    restart: usize,
+    r: &mut R,
+) -> Result<Run, Box<KMeansError>> {
+    let metric = &params.metric;
+    let mut distance_evaluations = DistanceEvaluations::default();
+
+    let root = calculate_next_centroid(metric, &points.iter().collect::<Vec<_>>());
+    distance_evaluations.computed += points.len() as u64;
+    let (mut clustering, _) = complete_clustering(
+        points,
+        vec![root],
+        vec![0; points.len()],
+        params.empty_cluster,
+        metric,
+    )?;
+    let mut tree = SplitTree {
+        nodes: vec![SplitNode {
+            label: 0,
+            centroid: clustering.centroids[0].clone(),
+            size: points.len(),
+            inertia: clustering.inertia,
+            parent: None,
+            children: None,
+            split: None,
+        }],
+    };
+    // node currently holding each label
+    let mut leaves = vec![0];
+
+    let mut history = HistoryRecorder::new(params.history, *metric);
+    history.record(0, &clustering);
+
+    // every split is 2-means on the members of one cluster, seeded from those
+    // members alone rather than the bounds of the whole input. a split is
+    // initialized once, restarts repeat the whole run instead
+    let halving = KMeans {
+        k: 2,
+        n_init: 1,
+        bounds: None,
+        ..params.clone()
+    };
+    for split in 1..params.k {
+        params.report(Progress::Iteration {
+            restart,
+            iteration: split,
+        });
+
+        // the cluster with the largest inertia, among those with points to
+        // spare, breaking ties toward the lowest label
+        let target = leaves
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| tree.nodes[**node].size > 1)
+            .fold((0, f64::MIN), |acc, (label, node)| {
+                match tree.nodes[*node].inertia > acc.1 {
+                    true => (label, tree.nodes[*node].inertia),
+                    false => acc,
+                }
+            })
+            .0;
+        let members: Vec<usize> = (0..points.len())
+            .filter(|ndx| clustering.labels[*ndx] == target)
+            .collect();
+        let subset: Vec<Point> = members.iter().map(|ndx| points[*ndx].clone()).collect();
+
+        let halves = bisect(&halving, &subset, &mut distance_evaluations, r)?;
+        let inertias = half_inertias(metric, &subset, &halves);
+
+        // the second half takes the next label
+        for (ndx, half) in members.iter().zip(&halves.labels) {
+            if *half == 1 {
+                clustering.labels[*ndx] = split;
+            }
+        }
+        clustering.centroids[target] = halves.centroids[0].clone();
+        clustering.centroids.push(halves.centroids[1].clone());
+
+        let parent = leaves[target];
+        let first = tree.nodes.len();
+        for (half, label) in [target, split].iter().enumerate() {
+            tree.nodes.push(SplitNode {
+                label: *label,
+                centroid: halves.centroids[half].clone(),
+                size: halves.labels.iter().filter(|l| **l == half).count(),
+                inertia: inertias[half],
+                parent: Some(parent),
+                children: None,
+                split: None,
+            });
+        }
+        tree.nodes[parent].children = Some([first, first + 1]);
+        tree.nodes[parent].split = Some(split);
+        leaves[target] = first;
+        leaves.push(first + 1);
+
+        // no cluster is empty, so this only totals up the inertia in input order
+        clustering = complete_clustering(
+            points,
+            clustering.centroids,
+            clustering.labels,
+            params.empty_cluster,
+            metric,
+        )?
+        .0;
+        history.record(split, &clustering);
+    }
+
+    let iterations = params.k - 1;
+    params.report(Progress::Converged {
+        restart,
+        iterations,
+        reason: StopReason::ReachedK,
+    });
+    let convergence = Convergence {
+        converged: true,
+        iterations,
+        reason: StopReason::ReachedK,
+    };
+
+    let history = history.finish(iterations, &clustering);
+    clustering.split_tree = Some(tree);
+
+    Ok(Run {
+        clustering,
+        convergence,
+        history,
+        distance_evaluations,
+    })
+}
+
+// split points in two with 2-means, iterated as configured
+fn bisect<R: Rng + ?Sized>(
+    params: &KMeans,
+    points: &[Point],
+    distance_evaluations: &mut DistanceEvaluations,
+    r: &mut R,
+) -> Result<Clustering, Box<KMeansError>> {
+    let metric = &params.metric;
+    let mut regroup = |centroids: Vec<Point>| {
+        distance_evaluations.computed += (points.len() * centroids.len()) as u64;
+        regroup_points(points, centroids, params.empty_cluster, metric)
+    };
+
+    let mut clustering = regroup(initial_centroids(params, points, r)?)?;
+    for _ in 0..params.max_iterations {
+        let next_centroids: Vec<Point> = clustering
+            .members(points)
+            .par_iter()
+            .map(|cluster| calculate_next_centroid(metric, cluster))
+            .collect();
+
+        let next = regroup(next_centroids)?;
+        let stop_reason = params.stop_reason(&clustering, &next);
+        clustering = next;
+        if stop_reason.is_some() {
+            break;
+        }
+    }
+
+    Ok(clustering)
+}
+
+// inertia of each half of a split, summed in input order
+fn half_inertias(metric: &Metric, points: &[Point], halves: &Clustering) -> [f64; 2] {
+    let mut inertias = [0_f64; 2];
+    for (point, half) in points.iter().zip(&halves.labels) {
+        inertias[*half] += metric.distance(&halves.centroids[*half], point);
+    }
+
+    inertias
+}
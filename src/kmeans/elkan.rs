@@ -0,0 +1,199 @@
+use super::{Bounds, DistanceEvaluations};
+use crate::distance::{Distance, Metric};
+use crate::point::Point;
+use rayon::prelude::*;
+
+// bounds are only trusted to rule out a centroid when they do so by more than
+// this relative margin, absorbing rounding error accumulated across iterations
+const MARGIN: f64 = 1e-9;
+
+// Elkan's accelerated assignment step, from "Using the Triangle Inequality to
+// Accelerate k-Means" (2003). every point keeps an upper bound on the distance
+// to its own centroid and a lower bound on the distance to every centroid, and
+// only evaluates the distances its bounds cannot rule out.
+//
+// bounds are kept in a metric satisfying the triangle inequality, so under
+// squared Euclidean distance they hold plain Euclidean distances. a centroid is
+// only ruled out when it is strictly farther than the point's own, so ties are
+// broken toward the lowest index exactly as in the Lloyd assignment
+pub(super) struct Elkan {
+    k: usize,
+    centroids: Vec<Point>,
+    labels: Vec<usize>,
+    upper: Vec<f64>,
+    // k lower bounds per point, row-major
+    lower: Vec<f64>,
+}
+
+impl Elkan {
+    // exact bounds for every point against centroids. points are labelled with
+    // their nearest centroid, unless labels are given
+    pub(super) fn new(
+        points: &[Point],
+        centroids: &[Point],
+        labels: Option<&[usize]>,
+        metric: &Metric,
+        evaluations: &mut DistanceEvaluations,
+    ) -> Self {
+        let k = centroids.len();
+        let mut lower = vec![0_f64; points.len() * k];
+        let (labels, upper): (Vec<usize>, Vec<f64>) = points
+            .par_iter()
+            .zip(lower.par_chunks_mut(k))
+            .enumerate()
+            .map(|(ndx, (point, row))| {
+                let distances: Vec<f64> = centroids
+                    .iter()
+                    .map(|c| metric.distance(c, point))
+                    .collect();
+                row.iter_mut()
+                    .zip(&distances)
+                    .for_each(|(l, d)| *l = bound(metric, *d));
+
+                let label = match labels {
+                    Some(labels) => labels[ndx],
+                    None => nearest(&distances),
+                };
+                (label, row[label])
+            })
+            .unzip();
+
+        evaluations.computed += (points.len() * k) as u64;
+        Self {
+            k,
+            centroids: centroids.to_vec(),
+            labels,
+            upper,
+            lower,
+        }
+    }
+}
+
+impl Bounds for Elkan {
+    fn labels(&self) -> &[usize] {
+        &self.labels
+    }
+
+    fn assign(
+        &mut self,
+        points: &[Point],
+        centroids: &[Point],
+        metric: &Metric,
+        evaluations: &mut DistanceEvaluations,
+    ) -> Vec<usize> {
+        let k = self.k;
+
+        // how far each centroid moved, half the distance between every pair of
+        // centroids, and half the distance from each to its closest other
+        let shifts: Vec<f64> = self
+            .centroids
+            .iter()
+            .zip(centroids)
+            .map(|(prev, next)| bound(metric, metric.distance(prev, next)))
+            .collect();
+        let half: Vec<f64> = (0..k * k)
+            .into_par_iter()
+            .map(|ndx| match (ndx / k, ndx % k) {
+                (i, j) if i == j => 0_f64,
+                (i, j) => bound(metric, metric.distance(&centroids[i], &centroids[j])) / 2_f64,
+            })
+            .collect();
+        let nearest_half: Vec<f64> = (0..k)
+            .map(|i| {
+                (0..k)
+                    .filter(|j| *j != i)
+                    .map(|j| half[i * k + j])
+                    .fold(f64::MAX, f64::min)
+            })
+            .collect();
+
+        let computed: u64 = points
+            .par_iter()
+            .zip(self.labels.par_iter_mut())
+            .zip(self.upper.par_iter_mut())
+            .zip(self.lower.par_chunks_mut(k))
+            .map(|(((point, label), upper), lower)| {
+                *upper += shifts[*label];
+                lower
+                    .iter_mut()
+                    .zip(&shifts)
+                    .for_each(|(l, shift)| *l = f64::max(0_f64, *l - shift));
+
+                // every other centroid is farther than twice the distance to its own
+                if rules_out(nearest_half[*label], *upper) {
+                    return 0;
+                }
+
+                let mut computed = 0;
+                let mut current: Option<f64> = None;
+                for j in 0..k {
+                    let pruned = |label: usize, upper: f64, lower: &[f64]| {
+                        rules_out(lower[j], upper) || rules_out(half[label * k + j], upper)
+                    };
+                    if j == *label || pruned(*label, *upper, lower) {
+                        continue;
+                    }
+
+                    // tighten the upper bound before evaluating any candidate
+                    let own = match current {
+                        Some(own) => own,
+                        None => {
+                            let own = metric.distance(&centroids[*label], point);
+                            computed += 1;
+                            *upper = bound(metric, own);
+                            lower[*label] = *upper;
+                            current = Some(own);
+
+                            if pruned(*label, *upper, lower) {
+                                continue;
+                            }
+                            own
+                        }
+                    };
+
+                    let candidate = metric.distance(&centroids[j], point);
+                    computed += 1;
+                    lower[j] = bound(metric, candidate);
+                    if candidate < own || (candidate == own && j < *label) {
+                        *label = j;
+                        *upper = lower[j];
+                        current = Some(candidate);
+                    }
+                }
+
+                computed
+            })
+            .sum();
+
+        evaluations.computed += computed;
+        evaluations.skipped += (points.len() * k) as u64 - computed;
+        self.centroids = centroids.to_vec();
+        self.labels.clone()
+    }
+}
+
+// whether a lower bound on the distance to a centroid rules it out, given an
+// upper bound on the distance to the point's own centroid
+pub(super) fn rules_out(lower: f64, upper: f64) -> bool {
+    lower - upper > MARGIN * f64::max(lower.abs(), upper.abs())
+}
+
+// distance under metric, expressed in a metric satisfying the triangle inequality
+pub(super) fn bound(metric: &Metric, distance: f64) -> f64 {
+    match metric {
+        Metric::SquaredEuclidean => distance.sqrt(),
+        _ => distance,
+    }
+}
+
+// index of the smallest distance, breaking ties as nearest_centroid does
+pub(super) fn nearest(distances: &[f64]) -> usize {
+    distances
+        .iter()
+        .enumerate()
+        .fold((0, f64::MAX), |acc, (ndx, d)| match *d < acc.1 {
+            true => (ndx, *d),
+            false => acc,
+        })
+        .0
+}
@@ -0,0 +1,181 @@
+use super::{
+    Clustering, Convergence, DistanceEvaluations, HistoryRecorder, KMeans, Progress, Run,
+    StopReason,
+};
+use crate::distance::{Distance, Metric};
+use crate::point::Point;
+use rayon::prelude::*;
+
+// fuzzy c-means, from Bezdek's "Pattern Recognition with Fuzzy Objective
+// Function Algorithms" (1981). rather than belonging to a single cluster, every
+// point has a membership in each, between 0 and 1 and summing to 1 over the
+// clusters. memberships fall off with the distance to each centroid, the more
+// sharply the closer the fuzzifier is to 1, and each centroid moves to the mean
+// of every point weighted by its membership raised to the fuzzifier.
+//
+// together these minimize the fuzzy objective, the total distance from every
+// point to every centroid weighted by membership raised to the fuzzifier,
+// which stands in for inertia. hard labels put each point in the cluster it
+// belongs to the most, which is its nearest centroid's
+pub(super) fn execute_once(
+    params: &KMeans,
+    fuzzifier: f64,
+    points: &[Point],
+    restart: usize,
+    initial_centroids: Vec<Point>,
+) -> Run {
+    let metric = &params.metric;
+    let mut distance_evaluations = DistanceEvaluations::default();
+    let mut regroup = |centroids: Vec<Point>| {
+        distance_evaluations.computed += (points.len() * centroids.len()) as u64;
+        assign(metric, fuzzifier, points, centroids)
+    };
+    let mut clustering = regroup(initial_centroids);
+
+    let mut history = HistoryRecorder::new(params.history, *metric);
+    history.record(0, &clustering);
+
+    let mut convergence = Convergence {
+        converged: false,
+        iterations: 0,
+        reason: StopReason::MaxIterations,
+    };
+    for iter in 1..=params.max_iterations {
+        params.report(Progress::Iteration {
+            restart,
+            iteration: iter,
+        });
+
+        let next = regroup(update(fuzzifier, points, &clustering));
+        let stop_reason = params.stop_reason(&clustering, &next);
+        clustering = next;
+        history.record(iter, &clustering);
+        convergence.iterations = iter;
+
+        if let Some(reason) = stop_reason {
+            params.report(Progress::Converged {
+                restart,
+                iterations: iter,
+                reason,
+            });
+            convergence.converged = true;
+            convergence.reason = reason;
+            break;
+        }
+    }
+
+    let history = history.finish(convergence.iterations, &clustering);
+
+    Run {
+        clustering,
+        convergence,
+        history,
+        distance_evaluations,
+    }
+}
+
+// memberships of every point against centroids, along with the hard labels and
+// fuzzy objective they imply
+fn assign(metric: &Metric, fuzzifier: f64, points: &[Point], centroids: Vec<Point>) -> Clustering {
+    let exponent = 1_f64 / (fuzzifier - 1_f64);
+    let (memberships, objectives): (Vec<Vec<f64>>, Vec<f64>) = points
+        .par_iter()
+        .map(|point| {
+            let distances: Vec<f64> = centroids
+                .iter()
+                .map(|c| metric.distance(c, point))
+                .collect();
+            let membership = membership(&distances, exponent);
+            let objective = membership
+                .iter()
+                .zip(&distances)
+                .fold(0_f64, |acc, (u, d)| acc + u.powf(fuzzifier) * d);
+            (membership, objective)
+        })
+        .unzip();
+
+    // the largest membership, breaking ties toward the lowest index
+    let labels = memberships
+        .iter()
+        .map(|membership| {
+            membership
+                .iter()
+                .enumerate()
+                .fold((0, f64::MIN), |acc, (ndx, u)| match *u > acc.1 {
+                    true => (ndx, *u),
+                    false => acc,
+                })
+                .0
+        })
+        .collect();
+
+    // objectives are summed in input order, independent of the number of threads
+    let inertia = objectives.iter().fold(0_f64, |acc, o| acc + o);
+
+    Clustering {
+        centroids,
+        labels,
+        inertia,
+        medoids: None,
+        memberships: Some(memberships),
+        split_tree: None,
+    }
+}
+
+// membership of a point in each cluster given its distance to each centroid,
+// inversely proportional to the distance raised to 1 / (fuzzifier - 1). a
+// point on top of one or more centroids belongs to those alone, split evenly
+pub(super) fn membership(distances: &[f64], exponent: f64) -> Vec<f64> {
+    let coincident = distances.iter().filter(|d| **d == 0_f64).count();
+    if coincident > 0 {
+        return distances
+            .iter()
+            .map(|d| match *d == 0_f64 {
+                true => 1_f64 / coincident as f64,
+                false => 0_f64,
+            })
+            .collect();
+    }
+
+    // ratios against a far centroid may overflow to infinity, which only
+    // rounds the membership down to 0
+    distances
+        .iter()
+        .map(|d| {
+            1_f64
+                / distances
+                    .iter()
+                    .fold(0_f64, |acc, other| acc + (d / other).powf(exponent))
+        })
+        .collect()
+}
+
+// each centroid is the mean of every point weighted by its membership raised
+// to the fuzzifier, summed in input order so the result does not depend on the
+// number of threads. a centroid every membership has underflowed away from
+// stays where it is
+fn update(fuzzifier: f64, points: &[Point], clustering: &Clustering) -> Vec<Point> {
+    let memberships = clustering.memberships.as_ref().unwrap();
+    clustering
+        .centroids
+        .par_iter()
+        .enumerate()
+        .map(|(ndx, centroid)| {
+            let mut sum = Point::zero(centroid.dim());
+            let mut weight = 0_f64;
+            for (point, membership) in points.iter().zip(memberships) {
+                let w = membership[ndx].powf(fuzzifier);
+                weight += w;
+                sum.coords
+                    .iter_mut()
+                    .zip(&point.coords)
+                    .for_each(|(s, c)| *s += w * c);
+            }
+
+            match weight > 0_f64 {
+                true => Point::new(sum.coords.iter().map(|s| s / weight).collect()),
+                false => centroid.clone(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,160 @@
+use super::elkan::{bound, nearest, rules_out};
+use super::{Bounds, DistanceEvaluations};
+use crate::distance::{Distance, Metric};
+use crate::point::Point;
+use rayon::prelude::*;
+
+// Hamerly's accelerated assignment step, from "Making k-means Even Faster"
+// (2010). every point keeps an upper bound on the distance to its own centroid
+// and a single lower bound on the distance to its second nearest, and only
+// rescans every centroid when the two bounds overlap. this needs far less
+// memory than Elkan's k lower bounds per point, and is usually faster for
+// low-dimensional data and moderate k.
+//
+// as with Elkan, bounds are kept in a metric satisfying the triangle
+// inequality, and a rescan is only skipped when every other centroid is
+// strictly farther than the point's own, so labels match the Lloyd assignment
+pub(super) struct Hamerly {
+    centroids: Vec<Point>,
+    labels: Vec<usize>,
+    upper: Vec<f64>,
+    lower: Vec<f64>,
+}
+
+impl Hamerly {
+    // exact bounds for every point against centroids. points are labelled with
+    // their nearest centroid, unless labels are given
+    pub(super) fn new(
+        points: &[Point],
+        centroids: &[Point],
+        labels: Option<&[usize]>,
+        metric: &Metric,
+        evaluations: &mut DistanceEvaluations,
+    ) -> Self {
+        let ((labels, upper), lower) = points
+            .par_iter()
+            .enumerate()
+            .map(|(ndx, point)| {
+                let distances: Vec<f64> = centroids
+                    .iter()
+                    .map(|c| metric.distance(c, point))
+                    .collect();
+                let label = match labels {
+                    Some(labels) => labels[ndx],
+                    None => nearest(&distances),
+                };
+
+                let (upper, lower) = bounds(metric, &distances, label);
+                ((label, upper), lower)
+            })
+            .unzip();
+
+        evaluations.computed += (points.len() * centroids.len()) as u64;
+        Self {
+            centroids: centroids.to_vec(),
+            labels,
+            upper,
+            lower,
+        }
+    }
+}
+
+impl Bounds for Hamerly {
+    fn labels(&self) -> &[usize] {
+        &self.labels
+    }
+
+    fn assign(
+        &mut self,
+        points: &[Point],
+        centroids: &[Point],
+        metric: &Metric,
+        evaluations: &mut DistanceEvaluations,
+    ) -> Vec<usize> {
+        let k = centroids.len();
+
+        // how far each centroid moved, and which two moved the farthest
+        let shifts: Vec<f64> = self
+            .centroids
+            .iter()
+            .zip(centroids)
+            .map(|(prev, next)| bound(metric, metric.distance(prev, next)))
+            .collect();
+        let farthest = (0..k)
+            .max_by(|a, b| shifts[*a].total_cmp(&shifts[*b]).then(b.cmp(a)))
+            .unwrap();
+        let runner_up = (0..k)
+            .filter(|j| *j != farthest)
+            .map(|j| shifts[j])
+            .fold(0_f64, f64::max);
+
+        // half the distance from each centroid to its closest other
+        let nearest_half: Vec<f64> = (0..k)
+            .into_par_iter()
+            .map(|i| {
+                (0..k)
+                    .filter(|j| *j != i)
+                    .map(|j| bound(metric, metric.distance(&centroids[i], &centroids[j])))
+                    .fold(f64::MAX, f64::min)
+                    / 2_f64
+            })
+            .collect();
+
+        let computed: u64 = points
+            .par_iter()
+            .zip(self.labels.par_iter_mut())
+            .zip(self.upper.par_iter_mut())
+            .zip(self.lower.par_iter_mut())
+            .map(|(((point, label), upper), lower)| {
+                *upper += shifts[*label];
+                *lower -= match *label == farthest {
+                    true => runner_up,
+                    false => shifts[farthest],
+                };
+
+                // every other centroid is farther than the point's own
+                let threshold = f64::max(nearest_half[*label], *lower);
+                if rules_out(threshold, *upper) {
+                    return 0;
+                }
+
+                let own = metric.distance(&centroids[*label], point);
+                *upper = bound(metric, own);
+                if rules_out(threshold, *upper) {
+                    return 1;
+                }
+
+                let distances: Vec<f64> = (0..k)
+                    .map(|j| match j == *label {
+                        true => own,
+                        false => metric.distance(&centroids[j], point),
+                    })
+                    .collect();
+                *label = nearest(&distances);
+                let (next_upper, next_lower) = bounds(metric, &distances, *label);
+                *upper = next_upper;
+                *lower = next_lower;
+
+                k as u64
+            })
+            .sum();
+
+        evaluations.computed += computed;
+        evaluations.skipped += (points.len() * k) as u64 - computed;
+        self.centroids = centroids.to_vec();
+        self.labels.clone()
+    }
+}
+
+// upper bound on the distance to the labelled centroid, and lower bound on the
+// distance to any other, given exact distances to every centroid
+fn bounds(metric: &Metric, distances: &[f64], label: usize) -> (f64, f64) {
+    let second = distances
+        .iter()
+        .enumerate()
+        .filter(|(j, _)| *j != label)
+        .map(|(_, d)| *d)
+        .fold(f64::MAX, f64::min);
+
+    (bound(metric, distances[label]), bound(metric, second))
+}
@@ -0,0 +1,266 @@
+use super::elkan::rules_out;
+use super::DistanceEvaluations;
+use crate::distance::{Distance, Metric};
+use crate::point::Point;
+
+// cells with no more points than this are not split any further
+const LEAF_SIZE: usize = 16;
+
+// cells with more points than this filter their two halves in parallel
+const PARALLEL_SIZE: usize = 4096;
+
+// the filtering algorithm of Kanungo et al., from "An Efficient k-Means
+// Clustering Algorithm: Analysis and Implementation" (2002). a KD-tree over the
+// input points is built once, and every assignment step walks it with a set of
+// candidate centroids, dropping each candidate that is farther than another
+// from every point of a cell. once a single candidate remains, every point in
+// the cell is labelled without evaluating a single distance.
+//
+// a candidate is only dropped when it is strictly farther for every point in
+// the cell, and leaves compare their points against the surviving candidates in
+// index order, so labels match the Lloyd assignment exactly
+pub(super) struct KdTree {
+    // input point indices, ordered so that every cell covers a contiguous range
+    order: Vec<usize>,
+    nodes: Vec<Node>,
+}
+
+struct Node {
+    // tight bounding box of the points in the cell
+    lower: Point,
+    upper: Point,
+    start: usize,
+    end: usize,
+    children: Option<(usize, usize)>,
+}
+
+impl KdTree {
+    pub(super) fn new(points: &[Point]) -> Self {
+        // coordinates are copied into a single row-major buffer, sparing the
+        // build a pointer chase for every comparison
+        let dim = points[0].dim();
+        let coords: Vec<f64> = points
+            .iter()
+            .flat_map(|p| p.coords.iter().copied())
+            .collect();
+
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        let mut nodes = vec![];
+        build(&coords, dim, &mut order, 0, &mut nodes);
+
+        Self { order, nodes }
+    }
+
+    // label every point with its nearest centroid
+    pub(super) fn assign(
+        &self,
+        points: &[Point],
+        centroids: &[Point],
+        metric: &Metric,
+        evaluations: &mut DistanceEvaluations,
+    ) -> Vec<usize> {
+        let candidates: Vec<usize> = (0..centroids.len()).collect();
+        let mut ordered_labels = vec![0_usize; points.len()];
+        let filter = Filter {
+            tree: self,
+            points,
+            centroids,
+            metric,
+        };
+        let computed = filter.node(0, &candidates, &mut ordered_labels);
+
+        let mut labels = vec![0_usize; points.len()];
+        for (ndx, label) in self.order.iter().zip(ordered_labels) {
+            labels[*ndx] = label;
+        }
+
+        evaluations.computed += computed;
+        evaluations.skipped += (points.len() * centroids.len()) as u64 - computed;
+        labels
+    }
+}
+
+// split a cell on its widest dimension at the median point, returning the
+// index of the node covering order
+fn build(
+    coords: &[f64],
+    dim: usize,
+    order: &mut [usize],
+    start: usize,
+    nodes: &mut Vec<Node>,
+) -> usize {
+    let (mut lower, mut upper) = (vec![f64::MAX; dim], vec![f64::MIN; dim]);
+    for ndx in order.iter() {
+        for (d, c) in coords[ndx * dim..(ndx + 1) * dim].iter().enumerate() {
+            lower[d] = f64::min(lower[d], *c);
+            upper[d] = f64::max(upper[d], *c);
+        }
+    }
+    let (lower, upper) = (Point::new(lower), Point::new(upper));
+    let widest = (0..dim)
+        .max_by(|a, b| {
+            let span = |d: &usize| upper.coords[*d] - lower.coords[*d];
+            span(a).total_cmp(&span(b))
+        })
+        .unwrap();
+
+    let id = nodes.len();
+    nodes.push(Node {
+        lower,
+        upper,
+        start,
+        end: start + order.len(),
+        children: None,
+    });
+
+    let spread = nodes[id].upper.coords[widest] - nodes[id].lower.coords[widest];
+    if order.len() <= LEAF_SIZE || spread <= 0_f64 {
+        return id;
+    }
+
+    let mid = order.len() / 2;
+    order.select_nth_unstable_by(mid, |a, b| {
+        coords[a * dim + widest].total_cmp(&coords[b * dim + widest])
+    });
+    let (left, right) = order.split_at_mut(mid);
+    let left = build(coords, dim, left, start, nodes);
+    let right = build(coords, dim, right, start + mid, nodes);
+    nodes[id].children = Some((left, right));
+
+    id
+}
+
+// a single assignment step's walk over the tree
+struct Filter<'a> {
+    tree: &'a KdTree,
+    points: &'a [Point],
+    centroids: &'a [Point],
+    metric: &'a Metric,
+}
+
+impl Filter<'_> {
+    // label the points of a cell, given as labels in tree order, from the
+    // candidates that survived every enclosing cell. returns the number of
+    // point-to-centroid distances evaluated
+    fn node(&self, id: usize, candidates: &[usize], labels: &mut [usize]) -> u64 {
+        let node = &self.tree.nodes[id];
+        if candidates.len() == 1 {
+            labels.iter_mut().for_each(|l| *l = candidates[0]);
+            return 0;
+        }
+
+        let (left, right) = match node.children {
+            Some(children) => children,
+            None => return self.leaf(node, candidates, labels),
+        };
+
+        // keep only candidates that some point of the cell may be closest to,
+        // measured against the candidate closest to the cell's midpoint
+        let midpoint = Point::new(
+            node.lower
+                .coords
+                .iter()
+                .zip(&node.upper.coords)
+                .map(|(lo, hi)| (lo + hi) / 2_f64)
+                .collect(),
+        );
+        let closest = *candidates
+            .iter()
+            .min_by(|a, b| {
+                let to_mid = |c: &usize| self.metric.distance(&self.centroids[*c], &midpoint);
+                to_mid(a).total_cmp(&to_mid(b))
+            })
+            .unwrap();
+        let candidates: Vec<usize> = candidates
+            .iter()
+            .copied()
+            .filter(|c| *c == closest || !self.farther(node, *c, closest))
+            .collect();
+        if candidates.len() == 1 {
+            labels.iter_mut().for_each(|l| *l = candidates[0]);
+            return 0;
+        }
+
+        let split = self.tree.nodes[left].end - node.start;
+        let (left_labels, right_labels) = labels.split_at_mut(split);
+        match node.end - node.start > PARALLEL_SIZE {
+            true => {
+                let (l, r) = rayon::join(
+                    || self.node(left, &candidates, left_labels),
+                    || self.node(right, &candidates, right_labels),
+                );
+                l + r
+            }
+            false => {
+                self.node(left, &candidates, left_labels)
+                    + self.node(right, &candidates, right_labels)
+            }
+        }
+    }
+
+    // compare every point of a leaf against the remaining candidates
+    fn leaf(&self, node: &Node, candidates: &[usize], labels: &mut [usize]) -> u64 {
+        let order = &self.tree.order[node.start..node.end];
+        for (label, ndx) in labels.iter_mut().zip(order) {
+            *label = candidates
+                .iter()
+                .fold((0, f64::MAX), |acc, c| {
+                    let d = self
+                        .metric
+                        .distance(&self.centroids[*c], &self.points[*ndx]);
+                    match d < acc.1 {
+                        true => (*c, d),
+                        false => acc,
+                    }
+                })
+                .0;
+        }
+
+        (order.len() * candidates.len()) as u64
+    }
+
+    // whether candidate is strictly farther than closest from every point of
+    // the cell. under squared Euclidean distance the difference between the
+    // two is linear over the cell, so it suffices to check the single corner
+    // lying farthest toward candidate. other metrics compare the candidate's
+    // distance to the nearest point of the cell with closest's to the farthest
+    fn farther(&self, node: &Node, candidate: usize, closest: usize) -> bool {
+        let (c, z) = (&self.centroids[candidate], &self.centroids[closest]);
+        let corner = |toward: &dyn Fn(usize) -> bool| {
+            Point::new(
+                (0..node.lower.dim())
+                    .map(|d| match toward(d) {
+                        true => node.upper.coords[d],
+                        false => node.lower.coords[d],
+                    })
+                    .collect(),
+            )
+        };
+
+        match self.metric {
+            Metric::SquaredEuclidean => {
+                let vertex = corner(&|d| c.coords[d] > z.coords[d]);
+                rules_out(
+                    self.metric.distance(c, &vertex),
+                    self.metric.distance(z, &vertex),
+                )
+            }
+            _ => {
+                let nearest = Point::new(
+                    c.coords
+                        .iter()
+                        .zip(node.lower.coords.iter().zip(&node.upper.coords))
+                        .map(|(v, (lo, hi))| v.clamp(*lo, *hi))
+                        .collect(),
+                );
+                let farthest = corner(&|d| {
+                    z.coords[d] - node.lower.coords[d] < node.upper.coords[d] - z.coords[d]
+                });
+                rules_out(
+                    self.metric.distance(c, &nearest),
+                    self.metric.distance(z, &farthest),
+                )
+            }
+        }
+    }
+}
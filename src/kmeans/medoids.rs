@@ -0,0 +1,294 @@
+use super::{
+    init_indices_plus_plus, nearest_centroid, Clustering, Convergence, DistanceEvaluations,
+    HistoryRecorder, Init, KMeans, KMeansError, Medoids, Progress, Run, StopReason,
+};
+use crate::distance::{Distance, Metric};
+use crate::point::Point;
+use rand::seq::index;
+use rand::Rng;
+use rayon::prelude::*;
+
+// a swap is only taken when it lowers the inertia by more than this fraction
+// of it, so rounding error cannot cycle between equivalent swaps
+const MIN_GAIN: f64 = 1e-9;
+
+// k-medoids, where every cluster center is the input point with the smallest
+// total distance to the other members of its cluster. centers can be read back
+// as real observations, and any metric works since no center is ever averaged.
+//
+// the alternating variant (Park and Jun, 2009) is k-means with the update step
+// replaced by choosing each cluster's medoid. PAM (Kaufman and Rousseeuw, 1990)
+// greedily BUILDs its initial medoids instead, then repeatedly performs the one
+// SWAP of a medoid for a non-medoid that lowers the inertia the most, until no
+// swap does. swaps are evaluated as in FastPAM1 (Schubert and Rousseeuw, 2019),
+// in a single pass over the points per candidate.
+//
+// every medoid belongs to its own cluster, so no cluster is ever left empty
+pub(super) fn execute_once<R: Rng + ?Sized>(
+    params: &KMeans,
+    method: Medoids,
+    points: &[Point],
+    restart: usize,
+    r: &mut R,
+) -> Result<Run, Box<KMeansError>> {
+    let metric = &params.metric;
+    let initial_medoids = match (method, params.init) {
+        (Medoids::Pam, _) => build(metric, params.k, points),
+        (Medoids::Alternating, Init::KMeansPlusPlus) => {
+            init_indices_plus_plus(metric, params.k, points, r)
+        }
+        (Medoids::Alternating, Init::Forgy) => index::sample(r, points.len(), params.k).into_vec(),
+        (Medoids::Alternating, init) => unreachable!("{} init does not pick medoids", init),
+    };
+
+    let mut distance_evaluations = DistanceEvaluations::default();
+    let mut regroup = |medoids: Vec<usize>| {
+        distance_evaluations.computed += (points.len() * medoids.len()) as u64;
+        assign(metric, points, medoids)
+    };
+    let mut clustering = regroup(initial_medoids);
+
+    let mut history = HistoryRecorder::new(params.history, *metric);
+    history.record(0, &clustering);
+
+    let mut convergence = Convergence {
+        converged: false,
+        iterations: 0,
+        reason: StopReason::MaxIterations,
+    };
+    for iter in 1..=params.max_iterations {
+        params.report(Progress::Iteration {
+            restart,
+            iteration: iter,
+        });
+
+        let next_medoids = match method {
+            Medoids::Alternating => Some(update(metric, points, &clustering)),
+            Medoids::Pam => swap(metric, points, &clustering),
+        };
+        let (next, stop_reason) = match next_medoids {
+            Some(medoids) => {
+                let next = regroup(medoids);
+                let stop_reason = match next.medoids == clustering.medoids {
+                    true => Some(StopReason::LabelsUnchanged),
+                    false => params.stop_reason(&clustering, &next),
+                };
+                (next, stop_reason)
+            }
+            None => {
+                // no swap is worth taking, so this iteration changed nothing
+                params.report(Progress::Converged {
+                    restart,
+                    iterations: iter - 1,
+                    reason: StopReason::NoImprovement,
+                });
+                convergence.converged = true;
+                convergence.reason = StopReason::NoImprovement;
+                break;
+            }
+        };
+
+        clustering = next;
+        history.record(iter, &clustering);
+        convergence.iterations = iter;
+
+        if let Some(reason) = stop_reason {
+            params.report(Progress::Converged {
+                restart,
+                iterations: iter,
+                reason,
+            });
+            convergence.converged = true;
+            convergence.reason = reason;
+            break;
+        }
+    }
+
+    let history = history.finish(convergence.iterations, &clustering);
+
+    Ok(Run {
+        clustering,
+        convergence,
+        history,
+        distance_evaluations,
+    })
+}
+
+// assign every point to its nearest medoid, except that a medoid always
+// belongs to its own cluster whatever it ties with
+fn assign(metric: &Metric, points: &[Point], medoids: Vec<usize>) -> Clustering {
+    let centroids: Vec<Point> = medoids.iter().map(|ndx| points[*ndx].clone()).collect();
+    let mut labels: Vec<usize> = points
+        .par_iter()
+        .map(|point| nearest_centroid(metric, &centroids, point).0)
+        .collect();
+    for (label, ndx) in medoids.iter().enumerate() {
+        labels[*ndx] = label;
+    }
+
+    // errors are summed in input order, independent of the number of threads
+    let errors: Vec<f64> = points
+        .par_iter()
+        .zip(&labels)
+        .map(|(point, label)| metric.distance(&centroids[*label], point))
+        .collect();
+    let inertia = errors.iter().fold(0_f64, |acc, error| acc + error);
+
+    Clustering {
+        centroids,
+        labels,
+        inertia,
+        medoids: Some(medoids),
+        memberships: None,
+        split_tree: None,
+    }
+}
+
+// each cluster's member with the smallest total distance to the others,
+// breaking ties toward the lowest input index
+pub(super) fn update(metric: &Metric, points: &[Point], clustering: &Clustering) -> Vec<usize> {
+    let mut members: Vec<Vec<usize>> = vec![vec![]; clustering.centroids.len()];
+    for (ndx, label) in clustering.labels.iter().enumerate() {
+        members[*label].push(ndx);
+    }
+
+    members
+        .par_iter()
+        .map(|cluster| {
+            cluster
+                .iter()
+                .fold((0, f64::MAX), |acc, candidate| {
+                    let total = cluster.iter().fold(0_f64, |acc, ndx| {
+                        acc + metric.distance(&points[*candidate], &points[*ndx])
+                    });
+                    match total < acc.1 {
+                        true => (*candidate, total),
+                        false => acc,
+                    }
+                })
+                .0
+        })
+        .collect()
+}
+
+// PAM BUILD: the first medoid is the point with the smallest total distance to
+// every other, and each next one the point lowering the total distance from
+// every point to its nearest medoid the most
+fn build(metric: &Metric, k: usize, points: &[Point]) -> Vec<usize> {
+    let mut nearest = vec![f64::MAX; points.len()];
+    let mut medoids: Vec<usize> = vec![];
+
+    for _ in 0..k {
+        let gains: Vec<f64> = (0..points.len())
+            .into_par_iter()
+            .map(|candidate| {
+                if medoids.contains(&candidate) {
+                    return f64::MIN;
+                }
+
+                let distances = points
+                    .iter()
+                    .map(|point| metric.distance(&points[candidate], point));
+                match medoids.is_empty() {
+                    true => -distances.sum::<f64>(),
+                    false => distances
+                        .zip(&nearest)
+                        .fold(0_f64, |acc, (d, n)| acc + f64::max(0_f64, n - d)),
+                }
+            })
+            .collect();
+        let next = gains
+            .iter()
+            .enumerate()
+            .fold((0, f64::MIN), |acc, (ndx, gain)| match *gain > acc.1 {
+                true => (ndx, *gain),
+                false => acc,
+            })
+            .0;
+
+        for (n, point) in nearest.iter_mut().zip(points) {
+            *n = f64::min(*n, metric.distance(&points[next], point));
+        }
+        medoids.push(next);
+    }
+
+    medoids
+}
+
+// PAM SWAP: the medoids after the single swap of a medoid for a non-medoid
+// lowering the inertia the most, or None when no swap lowers it.
+//
+// swapping medoid m for candidate c leaves every point with the closer of c
+// and its own medoid, unless its own medoid is m, in which case it gets the
+// closer of c and its nearest other medoid. one pass over the points per
+// candidate sums the change shared by every m, plus the extra change for
+// removing each point's own medoid
+fn swap(metric: &Metric, points: &[Point], clustering: &Clustering) -> Option<Vec<usize>> {
+    let medoids = clustering.medoids.as_ref().unwrap();
+    let k = medoids.len();
+
+    // distance from each point to its own medoid and to the nearest other
+    let (own, other): (Vec<f64>, Vec<f64>) = points
+        .par_iter()
+        .zip(&clustering.labels)
+        .map(|(point, label)| {
+            let other = clustering
+                .centroids
+                .iter()
+                .enumerate()
+                .filter(|(ndx, _)| ndx != label)
+                .map(|(_, medoid)| metric.distance(medoid, point))
+                .fold(f64::MAX, f64::min);
+            (metric.distance(&clustering.centroids[*label], point), other)
+        })
+        .unzip();
+
+    // best change in inertia and the medoid it removes, for every candidate
+    let changes: Vec<(f64, usize)> = (0..points.len())
+        .into_par_iter()
+        .map(|candidate| {
+            if medoids.contains(&candidate) {
+                return (f64::MAX, 0);
+            }
+
+            let mut shared = 0_f64;
+            let mut removal = vec![0_f64; k];
+            for (ndx, point) in points.iter().enumerate() {
+                let d = metric.distance(&points[candidate], point);
+                let kept = f64::min(d, own[ndx]) - own[ndx];
+                shared += kept;
+                removal[clustering.labels[ndx]] += f64::min(d, other[ndx]) - own[ndx] - kept;
+            }
+
+            removal
+                .iter()
+                .enumerate()
+                .fold((f64::MAX, 0), |acc, (medoid, change)| {
+                    match shared + change < acc.0 {
+                        true => (shared + change, medoid),
+                        false => acc,
+                    }
+                })
+        })
+        .collect();
+
+    let (candidate, (change, medoid)) =
+        changes
+            .into_iter()
+            .enumerate()
+            .fold((0, (f64::MAX, 0)), |acc, (candidate, change)| {
+                match change.0 < (acc.1).0 {
+                    true => (candidate, change),
+                    false => acc,
+                }
+            });
+
+    match change < -MIN_GAIN * clustering.inertia {
+        true => {
+            let mut next = medoids.clone();
+            next[medoid] = candidate;
+            Some(next)
+        }
+        false => None,
+    }
+}
@@ -0,0 +1,160 @@
+use super::{
+    complete_clustering, nearest_centroid, Convergence, DistanceEvaluations, HistoryRecorder,
+    KMeans, KMeansError, Progress, Run, Snapshot, StopReason,
+};
+use crate::point::Point;
+use rand::seq::index;
+use rand::Rng;
+use rayon::prelude::*;
+
+// mini-batch k-means, from Sculley's "Web-Scale K-Means Clustering" (2010).
+// every iteration samples a batch of points, assigns them to their nearest
+// centroids and pulls each centroid toward its batch members one at a time with
+// a learning rate of one over the number of points it has absorbed so far, so
+// every centroid is the running mean of the points it has been assigned.
+//
+// batch inertia is too noisy to converge on directly, so the run stops once an
+// exponentially weighted average of the per-point batch inertia has not
+// improved for max_no_improvement batches. intermediate snapshots carry no
+// labels and an inertia estimated from their batch; the final state is a full
+// assignment of every point, so its inertia compares directly with a Lloyd run
+pub(super) fn execute_once<R: Rng + ?Sized>(
+    params: &KMeans,
+    points: &[Point],
+    restart: usize,
+    mut centroids: Vec<Point>,
+    r: &mut R,
+) -> Result<Run, Box<KMeansError>> {
+    let metric = &params.metric;
+    let batch_size = usize::min(params.batch_size, points.len());
+    let mut distance_evaluations = DistanceEvaluations::default();
+
+    // each centroid state is recorded once the next batch has been assigned
+    // against it, estimating its inertia from that batch
+    let mut history = HistoryRecorder::new(params.history, *metric);
+
+    let mut counts = vec![0_usize; centroids.len()];
+    let mut smoothed = SmoothedInertia::new(batch_size, points.len());
+    let mut convergence = Convergence {
+        converged: false,
+        iterations: 0,
+        reason: StopReason::MaxIterations,
+    };
+    for iter in 1..=params.max_iterations {
+        params.report(Progress::Iteration {
+            restart,
+            iteration: iter,
+        });
+
+        // assign the batch against the centroids as they stood before it
+        let batch = index::sample(r, points.len(), batch_size).into_vec();
+        let assigned: Vec<(usize, f64)> = batch
+            .par_iter()
+            .map(|ndx| nearest_centroid(metric, &centroids, &points[*ndx]))
+            .collect();
+        distance_evaluations.computed += (batch_size * centroids.len()) as u64;
+
+        let batch_inertia = assigned.iter().fold(0_f64, |acc, (_, d)| acc + d);
+        history.record_snapshot(Snapshot {
+            iteration: iter - 1,
+            centroids: centroids.clone(),
+            labels: None,
+            inertia: batch_inertia * points.len() as f64 / batch_size as f64,
+            metric: *metric,
+            medoids: None,
+            memberships: None,
+        });
+
+        let prev = centroids.clone();
+        for (ndx, (label, _)) in batch.iter().zip(&assigned) {
+            counts[*label] += 1;
+            let rate = 1_f64 / counts[*label] as f64;
+            centroids[*label]
+                .coords
+                .iter_mut()
+                .zip(&points[*ndx].coords)
+                .for_each(|(c, x)| *c += rate * (x - *c));
+        }
+        convergence.iterations = iter;
+
+        let shift = prev
+            .iter()
+            .zip(&centroids)
+            .map(|(a, b)| a.sum_squared_error(b).sqrt())
+            .fold(0_f64, f64::max);
+        let stale = smoothed.update(batch_inertia / batch_size as f64);
+        let stop_reason = match params.max_centroid_shift {
+            Some(max_shift) if shift <= max_shift => Some(StopReason::CentroidShift),
+            _ if stale >= params.max_no_improvement => Some(StopReason::NoImprovement),
+            _ => None,
+        };
+
+        if let Some(reason) = stop_reason {
+            params.report(Progress::Converged {
+                restart,
+                iterations: iter,
+                reason,
+            });
+            convergence.converged = true;
+            convergence.reason = reason;
+            break;
+        }
+    }
+
+    // a full assignment of every point gives the final labels and inertia
+    let labels: Vec<usize> = points
+        .par_iter()
+        .map(|point| nearest_centroid(metric, &centroids, point).0)
+        .collect();
+    distance_evaluations.computed += (points.len() * centroids.len()) as u64;
+    let (clustering, _) =
+        complete_clustering(points, centroids, labels, params.empty_cluster, metric)?;
+
+    let history = history.finish(convergence.iterations, &clustering);
+
+    Ok(Run {
+        clustering,
+        convergence,
+        history,
+        distance_evaluations,
+    })
+}
+
+// exponentially weighted average of the per-point batch inertia, weighting
+// each batch by twice its share of the input as scikit-learn does
+struct SmoothedInertia {
+    alpha: f64,
+    value: Option<f64>,
+    best: f64,
+    stale: usize,
+}
+
+impl SmoothedInertia {
+    fn new(batch_size: usize, num_points: usize) -> Self {
+        Self {
+            alpha: f64::min(1_f64, 2_f64 * batch_size as f64 / (num_points + 1) as f64),
+            value: None,
+            best: f64::MAX,
+            stale: 0,
+        }
+    }
+
+    // fold in the next batch, returning how many batches in a row have
+    // failed to improve on the lowest average seen
+    fn update(&mut self, batch_inertia: f64) -> usize {
+        let value = match self.value {
+            Some(value) => value * (1_f64 - self.alpha) + batch_inertia * self.alpha,
+            None => batch_inertia,
+        };
+        self.value = Some(value);
+
+        match value < self.best {
+            true => {
+                self.best = value;
+                self.stale = 0;
+            }
+            false => self.stale += 1,
+        }
+        self.stale
+    }
+}
@@ -1,94 +1,1627 @@
-use crate::cli::Config;
-use crate::point::{generate_point, Centroid, Point};
-use std::collections::HashMap;
+use crate::distance::{Distance, Metric};
+use crate::point::{generate_point, Point};
+use elkan::Elkan;
+use hamerly::Hamerly;
+use kdtree::KdTree;
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use serde::Serialize;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
 
-pub type Cluster<'a> = HashMap<Centroid, Vec<&'a Point>>;
+/// Strategy used to place the initial centroids before the first iteration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Init {
+    #[serde(rename = "random")]
+    Random,
+    #[serde(rename = "kmeans++")]
+    KMeansPlusPlus,
+    #[serde(rename = "forgy")]
+    Forgy,
+    #[serde(rename = "random-partition")]
+    RandomPartition,
+}
+
+impl Display for Init {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Init::Random => write!(f, "random"),
+            Init::KMeansPlusPlus => write!(f, "kmeans++"),
+            Init::Forgy => write!(f, "forgy"),
+            Init::RandomPartition => write!(f, "random-partition"),
+        }
+    }
+}
+
+// convert raw String input of the form "kmeans++" into Init
+impl FromStr for Init {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(Init::Random),
+            "kmeans++" | "k-means++" => Ok(Init::KMeansPlusPlus),
+            "forgy" => Ok(Init::Forgy),
+            "random-partition" => Ok(Init::RandomPartition),
+            _ => Err(format!(
+                "unknown init strategy '{}', expected one of: random, kmeans++, forgy, random-partition",
+                s
+            )),
+        }
+    }
+}
+
+/// What to do with a centroid that ends up with no points assigned to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum EmptyCluster {
+    #[serde(rename = "farthest")]
+    Farthest,
+    #[serde(rename = "split")]
+    Split,
+    #[serde(rename = "error")]
+    Error,
+}
+
+impl Display for EmptyCluster {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            EmptyCluster::Farthest => write!(f, "farthest"),
+            EmptyCluster::Split => write!(f, "split"),
+            EmptyCluster::Error => write!(f, "error"),
+        }
+    }
+}
+
+// convert raw String input of the form "farthest" into EmptyCluster
+impl FromStr for EmptyCluster {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "farthest" => Ok(EmptyCluster::Farthest),
+            "split" => Ok(EmptyCluster::Split),
+            "error" => Ok(EmptyCluster::Error),
+            _ => Err(format!(
+                "unknown empty cluster policy '{}', expected one of: farthest, split, error",
+                s
+            )),
+        }
+    }
+}
+
+/// How much of a run's iteration history to record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum History {
+    /// Centroids and per-point labels for initialization and every iteration.
+    #[serde(rename = "full")]
+    Full,
+    /// Centroids for initialization and every iteration, labels for the final state only.
+    #[serde(rename = "centroids")]
+    Centroids,
+    /// Only the final state.
+    #[serde(rename = "final")]
+    Final,
+}
+
+impl Display for History {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            History::Full => write!(f, "full"),
+            History::Centroids => write!(f, "centroids"),
+            History::Final => write!(f, "final"),
+        }
+    }
+}
+
+// convert raw String input of the form "centroids" into History
+impl FromStr for History {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(History::Full),
+            "centroids" => Ok(History::Centroids),
+            "final" => Ok(History::Final),
+            _ => Err(format!(
+                "unknown history mode '{}', expected one of: full, centroids, final",
+                s
+            )),
+        }
+    }
+}
+
+/// How the change in inertia between iterations is compared against the tolerance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ToleranceMode {
+    /// Absolute change in total squared error.
+    #[serde(rename = "absolute")]
+    Absolute,
+    /// Change in total squared error relative to the previous iteration's.
+    #[serde(rename = "relative")]
+    Relative,
+}
+
+impl Display for ToleranceMode {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            ToleranceMode::Absolute => write!(f, "absolute"),
+            ToleranceMode::Relative => write!(f, "relative"),
+        }
+    }
+}
+
+// convert raw String input of the form "relative" into ToleranceMode
+impl FromStr for ToleranceMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "absolute" => Ok(ToleranceMode::Absolute),
+            "relative" => Ok(ToleranceMode::Relative),
+            _ => Err(format!(
+                "unknown tolerance mode '{}', expected one of: absolute, relative",
+                s
+            )),
+        }
+    }
+}
+
+/// Strategy for the assignment step of each iteration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Algorithm {
+    /// Lloyd's algorithm, comparing every point against every centroid.
+    #[serde(rename = "lloyd")]
+    Lloyd,
+    /// Elkan's algorithm, skipping comparisons that triangle-inequality bounds
+    /// rule out. Produces the same labels as [`Algorithm::Lloyd`], and cannot be
+    /// used with [`Metric::Cosine`], which does not satisfy the triangle inequality.
+    #[serde(rename = "elkan")]
+    Elkan,
+    /// Hamerly's algorithm, a lighter variant of [`Algorithm::Elkan`] with a
+    /// single lower bound per point, usually fastest for low-dimensional data.
+    /// Produces the same labels as [`Algorithm::Lloyd`] and cannot be used with
+    /// [`Metric::Cosine`] either.
+    #[serde(rename = "hamerly")]
+    Hamerly,
+    /// Mini-batch k-means, moving centroids toward random batches of points
+    /// instead of assigning every point every iteration. Only supports
+    /// [`Metric::SquaredEuclidean`], and ignores the inertia tolerance and label
+    /// stability criteria in favor of stopping once the smoothed batch inertia
+    /// stops improving. Each iteration processes one batch.
+    #[serde(rename = "minibatch")]
+    MiniBatch,
+    /// Kanungo et al.'s filtering algorithm, which builds a KD-tree over the
+    /// input points once per run and labels whole cells at a time once a single
+    /// candidate centroid remains for them. Usually fastest for low-dimensional
+    /// data with many points. Produces the same labels as [`Algorithm::Lloyd`],
+    /// and cannot be used with [`Metric::Cosine`].
+    #[serde(rename = "kdtree")]
+    KdTree,
+}
+
+impl Display for Algorithm {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Algorithm::Lloyd => write!(f, "lloyd"),
+            Algorithm::Elkan => write!(f, "elkan"),
+            Algorithm::Hamerly => write!(f, "hamerly"),
+            Algorithm::MiniBatch => write!(f, "minibatch"),
+            Algorithm::KdTree => write!(f, "kdtree"),
+        }
+    }
+}
+
+// convert raw String input of the form "elkan" into Algorithm
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lloyd" => Ok(Algorithm::Lloyd),
+            "elkan" => Ok(Algorithm::Elkan),
+            "hamerly" => Ok(Algorithm::Hamerly),
+            "minibatch" => Ok(Algorithm::MiniBatch),
+            "kdtree" => Ok(Algorithm::KdTree),
+            _ => Err(format!(
+                "unknown algorithm '{}', expected one of: lloyd, elkan, hamerly, minibatch, kdtree",
+                s
+            )),
+        }
+    }
+}
+
+/// Clustering around medoids, cluster centers that are themselves input
+/// points, instead of the centers of [`Distance::center`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Medoids {
+    /// k-means with the update step replaced by choosing each cluster's
+    /// medoid, the member with the smallest total distance to the others.
+    /// Initial medoids come from [`Init::KMeansPlusPlus`] or [`Init::Forgy`].
+    #[serde(rename = "alternating")]
+    Alternating,
+    /// Partitioning Around Medoids: greedy BUILD initialization, then SWAP
+    /// iterations each exchanging the medoid and non-medoid that lower the
+    /// total distance the most. Slower than [`Medoids::Alternating`], but
+    /// usually finds a better clustering. Ignores the configured [`Init`].
+    #[serde(rename = "pam")]
+    Pam,
+}
+
+impl Display for Medoids {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Medoids::Alternating => write!(f, "alternating"),
+            Medoids::Pam => write!(f, "pam"),
+        }
+    }
+}
+
+// convert raw String input of the form "pam" into Medoids
+impl FromStr for Medoids {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "alternating" => Ok(Medoids::Alternating),
+            "pam" => Ok(Medoids::Pam),
+            _ => Err(format!(
+                "unknown medoids method '{}', expected one of: alternating, pam",
+                s
+            )),
+        }
+    }
+}
+
+/// Why a run stopped iterating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// The change in inertia fell below the tolerance.
+    InertiaTolerance,
+    /// No centroid moved farther than the configured maximum shift.
+    CentroidShift,
+    /// No point changed cluster.
+    LabelsUnchanged,
+    /// The smoothed mini-batch inertia stopped improving, or no PAM swap
+    /// lowers the inertia any further.
+    NoImprovement,
+    /// The iteration limit was reached without meeting any other criterion.
+    MaxIterations,
+    /// Bisecting k-means split its way to k clusters.
+    ReachedK,
+}
+
+/// Whether, when and why a run stopped iterating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Convergence {
+    pub converged: bool,
+    /// Number of iterations performed after initialization.
+    pub iterations: usize,
+    pub reason: StopReason,
+}
+
+/// Point-to-centroid distances evaluated by the assignment steps of a run, and
+/// how many of the `points × k` comparisons an accelerated algorithm skipped.
+/// Distances the accelerated algorithms evaluate between centroids, or against
+/// KD-tree cells, are bookkeeping and not counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DistanceEvaluations {
+    pub computed: u64,
+    pub skipped: u64,
+}
+
+/// Error raised when a k-means run cannot be performed or completed.
+#[derive(Debug)]
+pub struct KMeansError {
+    err_msg: String,
+}
+
+impl KMeansError {
+    fn new(msg: &str) -> Box<Self> {
+        Box::new(Self {
+            err_msg: msg.to_string(),
+        })
+    }
+}
+
+impl Display for KMeansError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.err_msg)
+    }
+}
+
+impl Error for KMeansError {
+    fn description(&self) -> &str {
+        &self.err_msg
+    }
+}
+
+/// Progress of a single restart, reported as a run goes to the callback set
+/// with [`KMeans::progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Progress {
+    /// The restart is about to perform an iteration, which is a mini-batch
+    /// under [`Algorithm::MiniBatch`] and a split for bisecting k-means.
+    Iteration { restart: usize, iteration: usize },
+    /// The restart stopped before the iteration limit.
+    Converged {
+        restart: usize,
+        iterations: usize,
+        reason: StopReason,
+    },
+}
+
+// the callback set with KMeans::progress, shared by every restart
+#[derive(Clone)]
+struct ProgressCallback(Arc<dyn Fn(Progress) + Send + Sync>);
+
+impl Debug for ProgressCallback {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "ProgressCallback")
+    }
+}
+
+/// Configuration for a k-means run, built up from [`KMeans::new`].
+///
+/// ```
+/// use kmeans_rs::{Init, KMeans, Point};
+///
+/// let points = vec![
+///     Point::new(vec![1.0, 1.0]),
+///     Point::new(vec![1.5, 2.0]),
+///     Point::new(vec![8.0, 8.0]),
+///     Point::new(vec![9.0, 8.5]),
+/// ];
+///
+/// let model = KMeans::new(2)
+///     .init(Init::KMeansPlusPlus)
+///     .seed(42)
+///     .fit(&points)
+///     .unwrap();
+///
+/// assert_eq!(2, model.centroids.len());
+/// assert_eq!(model.labels[0], model.labels[1]);
+/// assert_ne!(model.labels[0], model.labels[2]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct KMeans {
+    k: usize,
+    max_iterations: usize,
+    tolerance: f64,
+    tolerance_mode: ToleranceMode,
+    max_centroid_shift: Option<f64>,
+    stop_when_labels_stable: bool,
+    init: Init,
+    n_init: usize,
+    empty_cluster: EmptyCluster,
+    metric: Metric,
+    algorithm: Algorithm,
+    medoids: Option<Medoids>,
+    fuzzifier: Option<f64>,
+    bisecting: bool,
+    batch_size: usize,
+    max_no_improvement: usize,
+    history: History,
+    bounds: Option<(Point, Point)>,
+    seed: Option<u64>,
+    threads: Option<usize>,
+    progress: Option<ProgressCallback>,
+}
+
+impl KMeans {
+    /// Start configuring a run that partitions its input into `k` clusters.
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            max_iterations: 20,
+            tolerance: 0.01,
+            tolerance_mode: ToleranceMode::Absolute,
+            max_centroid_shift: None,
+            stop_when_labels_stable: false,
+            init: Init::Random,
+            n_init: 1,
+            empty_cluster: EmptyCluster::Farthest,
+            metric: Metric::SquaredEuclidean,
+            algorithm: Algorithm::Lloyd,
+            medoids: None,
+            fuzzifier: None,
+            bisecting: false,
+            batch_size: 1024,
+            max_no_improvement: 10,
+            history: History::Final,
+            bounds: None,
+            seed: None,
+            threads: None,
+            progress: None,
+        }
+    }
+
+    /// Maximum number of iterations to perform without converging (default 20).
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Change in total squared error below which a run has converged (default 0.01).
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Whether the tolerance applies to the absolute or relative change in
+    /// total squared error (default [`ToleranceMode::Absolute`]).
+    pub fn tolerance_mode(mut self, tolerance_mode: ToleranceMode) -> Self {
+        self.tolerance_mode = tolerance_mode;
+        self
+    }
+
+    /// Also converge once no centroid moves farther than `max_shift` in an iteration.
+    pub fn max_centroid_shift(mut self, max_shift: f64) -> Self {
+        self.max_centroid_shift = Some(max_shift);
+        self
+    }
+
+    /// Also converge once an iteration leaves every point in the same cluster.
+    pub fn stop_when_labels_stable(mut self, stop: bool) -> Self {
+        self.stop_when_labels_stable = stop;
+        self
+    }
+
+    /// Initial centroid placement strategy (default [`Init::Random`]).
+    pub fn init(mut self, init: Init) -> Self {
+        self.init = init;
+        self
+    }
+
+    /// Number of independent initializations to run, keeping the one with the
+    /// lowest inertia (default 1).
+    pub fn n_init(mut self, n_init: usize) -> Self {
+        self.n_init = n_init;
+        self
+    }
+
+    /// Policy for centroids left without points (default [`EmptyCluster::Farthest`]).
+    pub fn empty_cluster(mut self, empty_cluster: EmptyCluster) -> Self {
+        self.empty_cluster = empty_cluster;
+        self
+    }
+
+    /// Distance points are assigned to their nearest centroid by, which also
+    /// decides how centroids are updated (default [`Metric::SquaredEuclidean`]).
+    pub fn metric(mut self, metric: Metric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Strategy for the assignment step of each iteration (default [`Algorithm::Lloyd`]).
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Cluster around medoids chosen from the input points rather than centroids.
+    pub fn medoids(mut self, medoids: Medoids) -> Self {
+        self.medoids = Some(medoids);
+        self
+    }
+
+    /// Cluster with fuzzy c-means, giving every point a membership in each
+    /// cluster. The fuzzifier must be greater than 1: values near 1 approach
+    /// hard k-means, larger ones blur clusters together, and 2 is customary.
+    /// Only supports [`Metric::SquaredEuclidean`], and never leaves a cluster
+    /// empty. Inertia is then the fuzzy objective, the total distance to every
+    /// centroid weighted by membership raised to the fuzzifier.
+    pub fn fuzzy(mut self, fuzzifier: f64) -> Self {
+        self.fuzzifier = Some(fuzzifier);
+        self
+    }
+
+    /// Build the clusters top down with bisecting k-means: starting from a
+    /// single cluster, repeatedly split the one with the largest inertia in two
+    /// with 2-means until there are k. Each split is initialized and iterated
+    /// as configured, and the hierarchy of splits is kept in
+    /// [`Clustering::split_tree`].
+    pub fn bisecting(mut self, bisecting: bool) -> Self {
+        self.bisecting = bisecting;
+        self
+    }
+
+    /// Number of points sampled per iteration by [`Algorithm::MiniBatch`] (default 1024).
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Number of consecutive batches without an improvement in smoothed
+    /// inertia after which [`Algorithm::MiniBatch`] stops (default 10).
+    pub fn max_no_improvement(mut self, max_no_improvement: usize) -> Self {
+        self.max_no_improvement = max_no_improvement;
+        self
+    }
+
+    /// How much iteration history [`execute`] records (default [`History::Final`]).
+    pub fn history(mut self, history: History) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Region [`Init::Random`] places centroids in, as an inclusive lower and
+    /// exclusive upper bound, finite and with every coordinate of `lower` below
+    /// that of `upper`. Defaults to the bounding box of the input.
+    pub fn bounds(mut self, lower: Point, upper: Point) -> Self {
+        self.bounds = Some((lower, upper));
+        self
+    }
+
+    /// Seed for [`KMeans::fit`], making its results reproducible.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Number of threads [`execute`] runs restarts and the assignment and
+    /// update steps on. Defaults to the global rayon pool, sized to the machine.
+    /// Results do not depend on the number of threads.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Callback receiving the [`Progress`] of every restart as [`execute`]
+    /// runs, called from whichever thread runs the restart. Nothing is
+    /// reported by default.
+    pub fn progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(Progress) + Send + Sync + 'static,
+    {
+        self.progress = Some(ProgressCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Cluster `points`, returning the best fitted model found.
+    pub fn fit(&self, points: &[Point]) -> Result<KMeansModel, Box<KMeansError>> {
+        let mut r = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        let result = execute(self, points, &mut r)?;
+        Ok(KMeansModel::from(result))
+    }
+
+    // pass progress on to the callback, if there is one
+    fn report(&self, progress: Progress) {
+        if let Some(callback) = &self.progress {
+            (callback.0)(progress);
+        }
+    }
+
+    fn validate(&self, points: &[Point]) -> Result<(), Box<KMeansError>> {
+        if self.k < 1 {
+            return Err(KMeansError::new("kmeans-rs: k must be positive"));
+        }
+
+        if self.max_iterations < 1 {
+            return Err(KMeansError::new(
+                "kmeans-rs: no point in performing less than 1 iteration",
+            ));
+        }
+
+        if self.n_init < 1 {
+            return Err(KMeansError::new("kmeans-rs: n_init must be positive"));
+        }
+
+        if self.threads == Some(0) {
+            return Err(KMeansError::new("kmeans-rs: threads must be positive"));
+        }
+
+        if self.tolerance.is_nan() || self.tolerance < 0_f64 {
+            return Err(KMeansError::new("kmeans-rs: tolerance cannot be negative"));
+        }
+
+        if matches!(self.max_centroid_shift, Some(shift) if shift.is_nan() || shift < 0_f64) {
+            return Err(KMeansError::new(
+                "kmeans-rs: max centroid shift cannot be negative",
+            ));
+        }
+
+        if matches!(self.metric, Metric::Minkowski(p) if !p.is_finite() || p < 1_f64) {
+            return Err(KMeansError::new(
+                "kmeans-rs: minkowski order must be finite and at least 1",
+            ));
+        }
+
+        if self.batch_size < 1 {
+            return Err(KMeansError::new("kmeans-rs: batch size must be positive"));
+        }
+
+        if self.algorithm == Algorithm::MiniBatch && self.metric != Metric::SquaredEuclidean {
+            return Err(KMeansError::new(
+                "kmeans-rs: minibatch only supports the sqeuclidean metric",
+            ));
+        }
+
+        if matches!(self.algorithm, Algorithm::Elkan | Algorithm::Hamerly)
+            && self.metric == Metric::Cosine
+        {
+            return Err(KMeansError::new(&format!(
+                "kmeans-rs: {} requires a metric satisfying the triangle inequality, which cosine distance does not",
+                self.algorithm
+            )));
+        }
+
+        if self.medoids.is_some() && self.algorithm != Algorithm::Lloyd {
+            return Err(KMeansError::new(
+                "kmeans-rs: k-medoids performs its own assignment step and only supports the lloyd algorithm",
+            ));
+        }
+
+        if self.medoids == Some(Medoids::Alternating)
+            && matches!(self.init, Init::Random | Init::RandomPartition)
+        {
+            return Err(KMeansError::new(
+                "kmeans-rs: alternating k-medoids needs initial medoids drawn from the input points, use kmeans++ or forgy init",
+            ));
+        }
+
+        if let Some(fuzzifier) = self.fuzzifier {
+            if fuzzifier.is_nan() || fuzzifier <= 1_f64 || fuzzifier.is_infinite() {
+                return Err(KMeansError::new(
+                    "kmeans-rs: fuzzifier must be a finite number greater than 1",
+                ));
+            }
 
-const EPSILON: f64 = 0.01;
+            if self.metric != Metric::SquaredEuclidean {
+                return Err(KMeansError::new(
+                    "kmeans-rs: fuzzy c-means only supports the sqeuclidean metric",
+                ));
+            }
+
+            if self.algorithm != Algorithm::Lloyd || self.medoids.is_some() {
+                return Err(KMeansError::new(
+                    "kmeans-rs: fuzzy c-means performs its own assignment and update steps, and cannot be combined with other algorithms or medoids",
+                ));
+            }
+        }
+
+        if self.bisecting
+            && (self.algorithm != Algorithm::Lloyd
+                || self.medoids.is_some()
+                || self.fuzzifier.is_some())
+        {
+            return Err(KMeansError::new(
+                "kmeans-rs: bisecting k-means splits clusters with lloyd's algorithm, and cannot be combined with other algorithms, medoids or fuzzy c-means",
+            ));
+        }
+
+        if self.algorithm == Algorithm::KdTree && self.metric == Metric::Cosine {
+            return Err(KMeansError::new(
+                "kmeans-rs: kdtree requires a metric growing with every coordinate difference, which cosine distance does not",
+            ));
+        }
+
+        if points.len() < self.k {
+            return Err(KMeansError::new(
+                "kmeans-rs: k is greater than the number of input points",
+            ));
+        }
+
+        let dim = points.first().map_or(0, Point::dim);
+        if dim == 0 || points.iter().any(|p| p.dim() != dim) {
+            return Err(KMeansError::new(
+                "kmeans-rs: input points must all have the same, positive dimension",
+            ));
+        }
+
+        if matches!(&self.bounds, Some((lower, upper)) if lower.dim() != dim || upper.dim() != dim)
+        {
+            return Err(KMeansError::new(
+                "kmeans-rs: bounds do not match the dimension of the input points",
+            ));
+        }
+
+        // random init samples every coordinate from the half-open range
+        // between the bounds, which must not be empty
+        if let Some((lower, upper)) = &self.bounds {
+            let empty = lower
+                .coords
+                .iter()
+                .zip(&upper.coords)
+                .any(|(l, u)| !l.is_finite() || !u.is_finite() || l >= u);
+            if empty {
+                return Err(KMeansError::new(
+                    "kmeans-rs: bounds must be finite, with every lower bound below its upper bound",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // first convergence criterion met moving from prev to next, if any
+    fn stop_reason(&self, prev: &Clustering, next: &Clustering) -> Option<StopReason> {
+        if self.stop_when_labels_stable && prev.labels == next.labels {
+            return Some(StopReason::LabelsUnchanged);
+        }
+
+        if let Some(max_shift) = self.max_centroid_shift {
+            let shift = prev
+                .centroids
+                .iter()
+                .zip(&next.centroids)
+                .map(|(a, b)| a.sum_squared_error(b).sqrt())
+                .fold(0_f64, f64::max);
+            if shift <= max_shift {
+                return Some(StopReason::CentroidShift);
+            }
+        }
+
+        let change = f64::abs(prev.inertia - next.inertia);
+        let change = match self.tolerance_mode {
+            ToleranceMode::Absolute => change,
+            ToleranceMode::Relative if prev.inertia > 0_f64 => change / prev.inertia,
+            ToleranceMode::Relative => 0_f64,
+        };
+        match change < self.tolerance {
+            true => Some(StopReason::InertiaTolerance),
+            false => None,
+        }
+    }
+}
+
+/// Final state of a fitted k-means run.
+#[derive(Debug, Clone)]
+pub struct KMeansModel {
+    /// Cluster centers, indexed by label.
+    pub centroids: Vec<Point>,
+    /// Index into `centroids` of the cluster each input point belongs to.
+    pub labels: Vec<usize>,
+    /// Total distance between the input points and their centroids, the
+    /// squared error under the default metric.
+    pub inertia: f64,
+    /// Whether, when and why the run stopped iterating.
+    pub convergence: Convergence,
+    /// Distance the model assigns points to centroids by.
+    pub metric: Metric,
+    /// Input index of each cluster's medoid, for k-medoids runs.
+    pub medoids: Option<Vec<usize>>,
+    /// Membership of each input point in each cluster, for fuzzy c-means runs.
+    pub memberships: Option<Vec<Vec<f64>>>,
+    /// Hierarchy of splits that produced the clusters, for bisecting runs.
+    pub split_tree: Option<SplitTree>,
+}
+
+impl KMeansModel {
+    /// Label of the centroid closest to `point`, which must have the same
+    /// dimension as the points the model was fit on.
+    pub fn predict(&self, point: &Point) -> usize {
+        nearest_centroid(&self.metric, &self.centroids, point).0
+    }
+}
+
+impl From<KMeansResult> for KMeansModel {
+    fn from(result: KMeansResult) -> Self {
+        Self {
+            centroids: result.clustering.centroids,
+            labels: result.clustering.labels,
+            inertia: result.clustering.inertia,
+            convergence: result.convergence,
+            metric: result.metric,
+            medoids: result.clustering.medoids,
+            memberships: result.clustering.memberships,
+            split_tree: result.clustering.split_tree,
+        }
+    }
+}
+
+/// Centroids and per-point cluster assignments at one step of a run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clustering {
+    /// Cluster centers, indexed by cluster.
+    pub centroids: Vec<Point>,
+    /// Index into `centroids` of the cluster each input point is assigned to.
+    pub labels: Vec<usize>,
+    /// Total distance between the input points and their centroids, the
+    /// squared error under the default metric.
+    pub inertia: f64,
+    /// Input index of each cluster's medoid, for k-medoids runs.
+    pub medoids: Option<Vec<usize>>,
+    /// Membership of each input point in each cluster, indexed by point and
+    /// then cluster, for fuzzy c-means runs. Each point's memberships sum to 1.
+    pub memberships: Option<Vec<Vec<f64>>>,
+    /// Hierarchy of splits that produced the clusters, for bisecting runs.
+    pub split_tree: Option<SplitTree>,
+}
+
+impl Clustering {
+    /// Input points assigned to each cluster, indexed by cluster.
+    pub fn members<'a>(&self, points: &'a [Point]) -> Vec<Vec<&'a Point>> {
+        let mut members = vec![vec![]; self.centroids.len()];
+        for (point, label) in points.iter().zip(&self.labels) {
+            members[*label].push(point);
+        }
+
+        members
+    }
+}
+
+/// Hierarchy of clusters built by bisecting k-means. The root holds every
+/// input point, and each split divides a cluster into two children, so
+/// cutting the tree after any number of splits gives a clustering for every k
+/// up to the one fitted.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SplitTree {
+    /// Every cluster the run went through, the root first and the two children
+    /// of each split appended in the order the splits happened.
+    pub nodes: Vec<SplitNode>,
+}
+
+/// A cluster in a [`SplitTree`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SplitNode {
+    /// Label of the cluster's points for as long as it remained unsplit. The
+    /// first child of a split keeps its parent's label, and the second takes
+    /// the next unused one.
+    pub label: usize,
+    pub centroid: Point,
+    /// Number of input points in the cluster.
+    pub size: usize,
+    /// Total distance between the cluster's points and its centroid.
+    pub inertia: f64,
+    /// Index of the node this cluster was split from, none for the root.
+    pub parent: Option<usize>,
+    /// Indices of the nodes this cluster was split into, none for a final cluster.
+    pub children: Option<[usize; 2]>,
+    /// Which split divided this cluster, counting from 1. There were
+    /// `split + 1` clusters after it.
+    pub split: Option<usize>,
+}
+
+impl SplitTree {
+    /// Nodes of the `k` clusters present after the first `k - 1` splits,
+    /// indexed by label. `k` must be between 1 and the number of clusters fitted.
+    pub fn cut(&self, k: usize) -> Vec<usize> {
+        let mut cut: Vec<usize> = (0..self.nodes.len())
+            .filter(|ndx| self.present(*ndx, k))
+            .collect();
+        cut.sort_by_key(|ndx| self.nodes[*ndx].label);
+
+        cut
+    }
+
+    /// Label of each input point after cutting the tree at `k` clusters, as in
+    /// [`SplitTree::cut`], given its label among all the clusters fitted.
+    pub fn cut_labels(&self, k: usize, labels: &[usize]) -> Vec<usize> {
+        // each final cluster belongs to its nearest ancestor present at k
+        let mut relabel = vec![0; self.nodes.len()];
+        for (ndx, node) in self.nodes.iter().enumerate() {
+            if node.children.is_none() {
+                let mut ancestor = ndx;
+                while !self.present(ancestor, k) {
+                    ancestor = self.nodes[ancestor].parent.unwrap();
+                }
+                relabel[node.label] = self.nodes[ancestor].label;
+            }
+        }
+
+        labels.iter().map(|label| relabel[*label]).collect()
+    }
+
+    // whether a node was one of the clusters after the first k - 1 splits
+    fn present(&self, ndx: usize, k: usize) -> bool {
+        let node = &self.nodes[ndx];
+        let created = node
+            .parent
+            .map_or(0, |parent| self.nodes[parent].split.unwrap());
+        let split_after = match node.split {
+            Some(split) => split >= k,
+            None => true,
+        };
+        created < k && split_after
+    }
+}
+
+/// Recorded state of a run after initialization (iteration 0) or an iteration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub iteration: usize,
+    /// Cluster centers, indexed by cluster.
+    pub centroids: Vec<Point>,
+    /// Cluster index of each input point, when the history mode records them.
+    pub labels: Option<Vec<u32>>,
+    /// Total distance between the input points and their centroids, the
+    /// squared error under the default metric. Intermediate mini-batch states
+    /// estimate it from the batch that followed them, and fuzzy c-means
+    /// reports its fuzzy objective instead.
+    pub inertia: f64,
+    /// Distance points were assigned to centroids by.
+    pub metric: Metric,
+    /// Input index of each cluster's medoid, for k-medoids runs.
+    pub medoids: Option<Vec<usize>>,
+    /// Membership of each input point in each cluster, for fuzzy c-means runs
+    /// whenever labels are recorded.
+    pub memberships: Option<Vec<Vec<f64>>>,
+}
+
+impl Snapshot {
+    fn new(iteration: usize, clustering: &Clustering, with_labels: bool, metric: Metric) -> Self {
+        Self {
+            iteration,
+            centroids: clustering.centroids.clone(),
+            labels: match with_labels {
+                true => Some(clustering.labels.iter().map(|l| *l as u32).collect()),
+                false => None,
+            },
+            inertia: clustering.inertia,
+            metric,
+            medoids: clustering.medoids.clone(),
+            memberships: match with_labels {
+                true => clustering.memberships.clone(),
+                false => None,
+            },
+        }
+    }
+
+    /// Cluster index of each input point. When labels were not recorded, each
+    /// point is assigned to its nearest centroid instead.
+    pub fn labels(&self, points: &[Point]) -> Vec<usize> {
+        match &self.labels {
+            Some(labels) => labels.iter().map(|l| *l as usize).collect(),
+            None => points
+                .iter()
+                .map(|p| nearest_centroid(&self.metric, &self.centroids, p).0)
+                .collect(),
+        }
+    }
+
+    /// Input points assigned to each cluster, indexed by cluster.
+    pub fn members<'a>(&self, points: &'a [Point]) -> Vec<Vec<&'a Point>> {
+        let mut members = vec![vec![]; self.centroids.len()];
+        for (point, label) in points.iter().zip(self.labels(points)) {
+            members[label].push(point);
+        }
+
+        members
+    }
+}
+
+/// Outcome of the best restart, along with the final inertia (total squared
+/// error) of every restart performed so the spread between them is visible.
+#[derive(Debug, Clone)]
+pub struct KMeansResult {
+    /// Final clustering of the best restart.
+    pub clustering: Clustering,
+    /// Whether, when and why the best restart stopped iterating.
+    pub convergence: Convergence,
+    /// States of the best restart, as recorded by the configured [`History`] mode.
+    pub history: Vec<Snapshot>,
+    pub inertia: f64,
+    pub best_restart: usize,
+    pub restart_inertia: Vec<f64>,
+    /// Distance points were assigned to centroids by.
+    pub metric: Metric,
+    /// Distances evaluated and skipped by the assignment steps of the best restart.
+    pub distance_evaluations: DistanceEvaluations,
+}
+
+// final state and recorded history of a single restart
+struct Run {
+    clustering: Clustering,
+    convergence: Convergence,
+    history: Vec<Snapshot>,
+    distance_evaluations: DistanceEvaluations,
+}
+
+// the states of a restart, recorded only as far as the history mode asks
+// rather than every clustering
+struct HistoryRecorder {
+    mode: History,
+    metric: Metric,
+    snapshots: Vec<Snapshot>,
+}
+
+impl HistoryRecorder {
+    fn new(mode: History, metric: Metric) -> Self {
+        Self {
+            mode,
+            metric,
+            snapshots: vec![],
+        }
+    }
+
+    // record an intermediate clustering, with its labels only in full mode
+    fn record(&mut self, iteration: usize, clustering: &Clustering) {
+        let labels = match self.mode {
+            History::Full => true,
+            History::Centroids => false,
+            History::Final => return,
+        };
+        self.snapshots
+            .push(Snapshot::new(iteration, clustering, labels, self.metric));
+    }
+
+    // record an intermediate state that is not a complete clustering as is,
+    // unless only the final state is kept
+    fn record_snapshot(&mut self, snapshot: Snapshot) {
+        if self.mode != History::Final {
+            self.snapshots.push(snapshot);
+        }
+    }
+
+    // the recorded history, ending in the final state with its labels and any
+    // memberships, whatever the mode. the final state replaces a recording of
+    // the same iteration
+    fn finish(mut self, iterations: usize, clustering: &Clustering) -> Vec<Snapshot> {
+        let last = Snapshot::new(iterations, clustering, true, self.metric);
+        match self.snapshots.last_mut() {
+            Some(recorded) if recorded.iteration == iterations => *recorded = last,
+            _ => self.snapshots.push(last),
+        }
+
+        self.snapshots
+    }
+}
+
+/// Run `params.n_init` independent initializations in parallel, keeping the
+/// run that finished with the lowest inertia. Each restart gets its own RNG
+/// seeded from `r` up front, so results do not depend on how rayon schedules them.
+pub fn execute<R: Rng + ?Sized>(
+    params: &KMeans,
+    points: &[Point],
+    r: &mut R,
+) -> Result<KMeansResult, Box<KMeansError>> {
+    params.validate(points)?;
+
+    let seeds: Vec<u64> = (0..params.n_init).map(|_| r.gen()).collect();
+    let execute_all = || {
+        // the tree only depends on the input points, so every restart shares it
+        let tree = match params.algorithm {
+            Algorithm::KdTree => Some(KdTree::new(points)),
+            _ => None,
+        };
+
+        seeds
+            .into_par_iter()
+            .enumerate()
+            .map(|(restart, seed)| {
+                let r = &mut StdRng::seed_from_u64(seed);
+                execute_once(params, points, tree.as_ref(), restart, r)
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    // a dedicated pool bounds every parallel step of the run, including the
+    // assignment and update steps nested inside each restart
+    let runs = match params.threads {
+        Some(threads) => ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|e| KMeansError::new(&format!("kmeans-rs: {}", e)))?
+            .install(execute_all)?,
+        None => execute_all()?,
+    };
+
+    let restart_inertia: Vec<f64> = runs.iter().map(|run| run.clustering.inertia).collect();
+    let (best_restart, best) = runs
+        .into_iter()
+        .enumerate()
+        .min_by(|(a, _), (b, _)| restart_inertia[*a].total_cmp(&restart_inertia[*b]))
+        .unwrap();
+
+    Ok(KMeansResult {
+        clustering: best.clustering,
+        convergence: best.convergence,
+        history: best.history,
+        inertia: restart_inertia[best_restart],
+        best_restart,
+        restart_inertia,
+        metric: params.metric,
+        distance_evaluations: best.distance_evaluations,
+    })
+}
 
 // https://www.analyticsvidhya.com/blog/2019/08/comprehensive-guide-k-means-clustering/
-pub fn execute<'a>(cfg: &Config, points: &'a Vec<Point>) -> Vec<Cluster<'a>> {
-    // initialize candidate centroids randomly and assign cluster colors
-    let initial_centroids = init_centroids(cfg);
+fn execute_once<R: Rng + ?Sized>(
+    params: &KMeans,
+    points: &[Point],
+    tree: Option<&KdTree>,
+    restart: usize,
+    r: &mut R,
+) -> Result<Run, Box<KMeansError>> {
+    if let Some(method) = params.medoids {
+        return medoids::execute_once(params, method, points, restart, r);
+    }
+
+    if params.bisecting {
+        return bisecting::execute_once(params, points, restart, r);
+    }
+
+    // initialize candidate centroids, indexed by cluster
+    let initial_centroids = initial_centroids(params, points, r)?;
+
+    if params.algorithm == Algorithm::MiniBatch {
+        return minibatch::execute_once(params, points, restart, initial_centroids, r);
+    }
+
+    if let Some(fuzzifier) = params.fuzzifier {
+        return Ok(fuzzy::execute_once(
+            params,
+            fuzzifier,
+            points,
+            restart,
+            initial_centroids,
+        ));
+    }
+
+    // assign points to centroids with the configured algorithm. the bounds of
+    // the accelerated algorithms carry over between iterations, and are rebuilt
+    // whenever the empty cluster policy moves points or centroids behind their back
+    let mut distance_evaluations = DistanceEvaluations::default();
+    let mut bounds: Option<Box<dyn Bounds>> = None;
+    let mut regroup = |centroids: Vec<Point>| {
+        let metric = &params.metric;
+        if params.algorithm == Algorithm::Lloyd {
+            distance_evaluations.computed += (points.len() * centroids.len()) as u64;
+            return regroup_points(points, centroids, params.empty_cluster, metric);
+        }
+
+        if let Some(tree) = tree {
+            let labels = tree.assign(points, &centroids, metric, &mut distance_evaluations);
+            let (clustering, _) =
+                complete_clustering(points, centroids, labels, params.empty_cluster, metric)?;
+            return Ok(clustering);
+        }
+
+        let labels = match bounds.as_mut() {
+            Some(state) => state.assign(points, &centroids, metric, &mut distance_evaluations),
+            None => {
+                let state = init_bounds(
+                    params.algorithm,
+                    points,
+                    &centroids,
+                    None,
+                    metric,
+                    &mut distance_evaluations,
+                );
+                let labels = state.labels().to_vec();
+                bounds = Some(state);
+                labels
+            }
+        };
+
+        let (clustering, repaired) =
+            complete_clustering(points, centroids, labels, params.empty_cluster, metric)?;
+        if repaired {
+            bounds = Some(init_bounds(
+                params.algorithm,
+                points,
+                &clustering.centroids,
+                Some(&clustering.labels),
+                metric,
+                &mut distance_evaluations,
+            ));
+        }
+        Ok(clustering)
+    };
 
     // perform the initial clustering using candidates
-    let (mut clusters, mut total_error) = regroup_points(points, initial_centroids);
+    let mut clustering = regroup(initial_centroids)?;
+
+    let mut history = HistoryRecorder::new(params.history, params.metric);
+    history.record(0, &clustering);
 
     // perform iterations
-    let mut cache = vec![clusters.clone()];
-    for iter in 1..=cfg.iterations {
-        eprintln!("kmeans-rs: calculating iteration {}", iter);
+    let mut convergence = Convergence {
+        converged: false,
+        iterations: 0,
+        reason: StopReason::MaxIterations,
+    };
+    for iter in 1..=params.max_iterations {
+        params.report(Progress::Iteration {
+            restart,
+            iteration: iter,
+        });
+
+        // each centroid is computed from its members in input order, so the
+        // update is identical however many threads share the clusters
+        let next_centroids: Vec<Point> = clustering
+            .members(points)
+            .par_iter()
+            .map(|cluster| calculate_next_centroid(&params.metric, cluster))
+            .collect();
+
+        let next = regroup(next_centroids)?;
+        let stop_reason = params.stop_reason(&clustering, &next);
+        clustering = next;
+        history.record(iter, &clustering);
+        convergence.iterations = iter;
 
-        let mut next_centroids = vec![];
-        for (centroid, cluster) in clusters {
-            let next_centroid = calculate_next_centroid(centroid, cluster);
-            next_centroids.push(next_centroid);
+        if let Some(reason) = stop_reason {
+            params.report(Progress::Converged {
+                restart,
+                iterations: iter,
+                reason,
+            });
+            convergence.converged = true;
+            convergence.reason = reason;
+            break;
         }
+    }
+
+    let history = history.finish(convergence.iterations, &clustering);
+
+    Ok(Run {
+        clustering,
+        convergence,
+        history,
+        distance_evaluations,
+    })
+}
+
+// candidate centroids placed by the configured init strategy, indexed by cluster
+fn initial_centroids<R: Rng + ?Sized>(
+    params: &KMeans,
+    points: &[Point],
+    r: &mut R,
+) -> Result<Vec<Point>, Box<KMeansError>> {
+    let centroids = match params.init {
+        Init::Random => {
+            let bounds = params
+                .bounds
+                .clone()
+                .unwrap_or_else(|| bounding_box(points));
+            init_centroids(params.k, (&bounds.0, &bounds.1), r)
+        }
+        Init::KMeansPlusPlus => init_centroids_plus_plus(&params.metric, params.k, points, r),
+        Init::Forgy => Point::select_initial_centroids(r, points, params.k).ok_or_else(|| {
+            KMeansError::new("kmeans-rs: forgy init requires at least k unique input points")
+        })?,
+        Init::RandomPartition => {
+            init_centroids_random_partition(&params.metric, params.k, points, r)
+        }
+    };
+
+    Ok(centroids)
+}
+
+// smallest box holding every input point. the upper bound is nudged outward
+// since generate_point treats it as exclusive
+fn bounding_box(points: &[Point]) -> (Point, Point) {
+    let dim = points[0].dim();
+    let lower = Point::new(
+        (0..dim)
+            .map(|d| points.iter().map(|p| p.coords[d]).fold(f64::MAX, f64::min))
+            .collect(),
+    );
+    let upper = Point::new(
+        (0..dim)
+            .map(|d| points.iter().map(|p| p.coords[d]).fold(f64::MIN, f64::max))
+            .map(|v| v + f64::max(v.abs(), 1_f64) * 1e-9)
+            .collect(),
+    );
 
-        let prev_error = total_error;
-        (clusters, total_error) = regroup_points(points, next_centroids);
-        cache.push(clusters.clone());
+    (lower, upper)
+}
+
+fn init_centroids<R: Rng + ?Sized>(k: usize, bounds: (&Point, &Point), r: &mut R) -> Vec<Point> {
+    // initialize candidate centroids randomly
+    (0..k).map(|_| generate_point(r, bounds)).collect()
+}
+
+// k-means++ seeding: the first centroid is a uniformly chosen input point,
+// each subsequent centroid is an input point sampled with probability
+// proportional to its distance from the nearest centroid chosen so far. that
+// is the classic D² weighting under the default squared Euclidean metric
+fn init_centroids_plus_plus<R: Rng + ?Sized>(
+    metric: &Metric,
+    k: usize,
+    points: &[Point],
+    r: &mut R,
+) -> Vec<Point> {
+    init_indices_plus_plus(metric, k, points, r)
+        .into_iter()
+        .map(|ndx| points[ndx].clone())
+        .collect()
+}
 
-        if f64::abs(prev_error - total_error) < EPSILON {
-            eprintln!("kmeans-rs: converged at iteration {}", iter);
-            return cache;
+// input indices of k distinct points chosen by k-means++ seeding
+fn init_indices_plus_plus<R: Rng + ?Sized>(
+    metric: &Metric,
+    k: usize,
+    points: &[Point],
+    r: &mut R,
+) -> Vec<usize> {
+    let first = r.gen_range(0..points.len());
+
+    // chosen points are never chosen again, even the origin under cosine
+    // distance, which is at distance 1 from itself
+    let mut min_errors: Vec<f64> = points
+        .iter()
+        .map(|p| metric.distance(&points[first], p))
+        .collect();
+    min_errors[first] = 0_f64;
+    let mut selected = vec![first];
+
+    for _ in 1..k {
+        // WeightedIndex rejects all-zero weights, which happens when every
+        // remaining input point duplicates an existing centroid. any point not
+        // chosen yet is then as good as another
+        let next = match WeightedIndex::new(&min_errors) {
+            Ok(dist) => dist.sample(r),
+            Err(_) => (0..points.len())
+                .filter(|ndx| !selected.contains(ndx))
+                .nth(r.gen_range(0..points.len() - selected.len()))
+                .unwrap(),
+        };
+
+        for (min_error, p) in min_errors.iter_mut().zip(points) {
+            *min_error = f64::min(*min_error, metric.distance(&points[next], p));
         }
+        min_errors[next] = 0_f64;
+        selected.push(next);
     }
 
-    cache
+    selected
 }
 
-fn init_centroids(cfg: &Config) -> Vec<Centroid> {
-    // initialize candidate centroids randomly and assign cluster colors
-    (1..=cfg.k)
-        .map(|color| Centroid {
-            p: generate_point(cfg.bounds()),
-            color: color,
-        })
+// random partition: every input point is assigned to a random cluster and the
+// initial centroids are the centers of those partitions. the first k points of
+// a shuffled ordering are dealt one per cluster so that no partition starts empty
+fn init_centroids_random_partition<R: Rng + ?Sized>(
+    metric: &Metric,
+    k: usize,
+    points: &[Point],
+    r: &mut R,
+) -> Vec<Point> {
+    let mut shuffled: Vec<&Point> = points.iter().collect();
+    shuffled.shuffle(r);
+
+    let mut partitions: Vec<Vec<&Point>> = vec![vec![]; k];
+    for (ndx, p) in shuffled.into_iter().enumerate() {
+        let selection = match ndx < k {
+            true => ndx,
+            false => r.gen_range(0..k),
+        };
+        partitions[selection].push(p);
+    }
+
+    partitions
+        .iter()
+        .map(|partition| calculate_next_centroid(metric, partition))
         .collect()
 }
 
-fn calculate_next_centroid(old_centroid: Centroid, cluster: Vec<&Point>) -> Centroid {
-    let size = cluster.len() as f64;
-    let x = cluster.iter().fold(0.0, |acc, v| acc + v.x) / size;
-    let y = cluster.iter().fold(0.0, |acc, v| acc + v.y) / size;
+// the point minimizing total distance to the cluster's members under metric,
+// which is their mean for the default squared Euclidean metric
+fn calculate_next_centroid(metric: &Metric, cluster: &[&Point]) -> Point {
+    metric.center(cluster)
+}
+
+// per-point distance bounds an accelerated algorithm carries between
+// assignment steps, letting it skip comparisons that cannot change a label
+trait Bounds: Send {
+    // current cluster index of each input point
+    fn labels(&self) -> &[usize];
+
+    // label every point with its nearest centroid after the centroids move,
+    // producing exactly the labels a full Lloyd assignment would
+    fn assign(
+        &mut self,
+        points: &[Point],
+        centroids: &[Point],
+        metric: &Metric,
+        evaluations: &mut DistanceEvaluations,
+    ) -> Vec<usize>;
+}
 
-    Centroid {
-        p: Point { x: x, y: y },
-        color: old_centroid.color,
+// exact bounds for every point against centroids for an accelerated algorithm.
+// points are labelled with their nearest centroid, unless labels are given
+fn init_bounds(
+    algorithm: Algorithm,
+    points: &[Point],
+    centroids: &[Point],
+    labels: Option<&[usize]>,
+    metric: &Metric,
+    evaluations: &mut DistanceEvaluations,
+) -> Box<dyn Bounds> {
+    match algorithm {
+        Algorithm::Elkan => Box::new(Elkan::new(points, centroids, labels, metric, evaluations)),
+        Algorithm::Hamerly => {
+            Box::new(Hamerly::new(points, centroids, labels, metric, evaluations))
+        }
+        Algorithm::Lloyd | Algorithm::MiniBatch | Algorithm::KdTree => {
+            unreachable!("{} keeps no bounds", algorithm)
+        }
     }
 }
 
-// compose a fresh mapping of input points to closest centroids.
-// returns the mapping with total min error for the iteration
-fn regroup_points(points: &Vec<Point>, centroids: Vec<Centroid>) -> (Cluster, f64) {
-    let mut next = Cluster::new();
-    let mut total_error = 0_f64;
+// compose a fresh assignment of input points to closest centroids, applying
+// the empty cluster policy so that every centroid keeps at least one member.
+// returns the assignment with total min error for the iteration
+fn regroup_points(
+    points: &[Point],
+    centroids: Vec<Point>,
+    policy: EmptyCluster,
+    metric: &Metric,
+) -> Result<Clustering, Box<KMeansError>> {
+    // label each point with the index of its best-fit centroid
+    let labels: Vec<usize> = points
+        .par_iter()
+        .map(|point| nearest_centroid(metric, &centroids, point).0)
+        .collect();
 
-    // group each point under best-fit centroid and capture the associated min error
-    for point in points {
-        let (centroid, min_error) = centroids.iter().fold(
-            (None, f64::MAX),
-            |acc: (Option<&Centroid>, f64), candidate: &Centroid| match acc {
-                (None, _) => (Some(candidate), candidate.p.sum_squared_error(point)),
-                (_, current_error) => {
-                    let candidate_error = candidate.p.sum_squared_error(point);
-                    match candidate_error < current_error {
-                        true => (Some(candidate), candidate_error),
-                        false => acc,
-                    }
-                }
-            },
-        );
+    let (clustering, _) = complete_clustering(points, centroids, labels, policy, metric)?;
+    Ok(clustering)
+}
+
+// apply the empty cluster policy to an assignment of points to centroids and
+// total up its error. also reports whether the policy had to move anything
+fn complete_clustering(
+    points: &[Point],
+    mut centroids: Vec<Point>,
+    mut labels: Vec<usize>,
+    policy: EmptyCluster,
+    metric: &Metric,
+) -> Result<(Clustering, bool), Box<KMeansError>> {
+    let mut sizes = vec![0_usize; centroids.len()];
+    labels.iter().for_each(|label| sizes[*label] += 1);
+
+    let mut repaired = false;
+    while let Some(empty) = sizes.iter().position(|size| *size == 0) {
+        repaired = true;
+        match policy {
+            EmptyCluster::Error => {
+                return Err(KMeansError::new(
+                    "kmeans-rs: a cluster lost all of its points",
+                ))
+            }
+            EmptyCluster::Farthest => reseed_farthest(
+                metric,
+                points,
+                &mut centroids,
+                &mut labels,
+                &mut sizes,
+                empty,
+            )?,
+            EmptyCluster::Split => split_largest(
+                metric,
+                points,
+                &mut centroids,
+                &mut labels,
+                &mut sizes,
+                empty,
+            )?,
+        }
+    }
+
+    // capture the error associated with each point's assignment. errors are
+    // computed in parallel but summed in input order, keeping the total
+    // independent of the number of threads
+    let errors: Vec<f64> = points
+        .par_iter()
+        .zip(&labels)
+        .map(|(point, label)| metric.distance(&centroids[*label], point))
+        .collect();
+    let inertia = errors.iter().fold(0_f64, |acc, error| acc + error);
+
+    let clustering = Clustering {
+        centroids,
+        labels,
+        inertia,
+        medoids: None,
+        memberships: None,
+        split_tree: None,
+    };
+    Ok((clustering, repaired))
+}
+
+// index of, and distance to, the centroid closest to point
+fn nearest_centroid(metric: &Metric, centroids: &[Point], point: &Point) -> (usize, f64) {
+    centroids.iter().enumerate().fold(
+        (0, f64::MAX),
+        |acc: (usize, f64), (ndx, candidate): (usize, &Point)| {
+            let candidate_error = metric.distance(candidate, point);
+            match candidate_error < acc.1 {
+                true => (ndx, candidate_error),
+                false => acc,
+            }
+        },
+    )
+}
 
-        total_error += min_error;
-        next.entry(centroid.unwrap().clone())
-            .or_insert(vec![])
-            .push(point);
+// move the empty centroid onto the point lying farthest from its own centroid,
+// considering only points whose cluster can spare a member
+fn reseed_farthest(
+    metric: &Metric,
+    points: &[Point],
+    centroids: &mut [Point],
+    labels: &mut [usize],
+    sizes: &mut [usize],
+    empty: usize,
+) -> Result<(), Box<KMeansError>> {
+    let farthest = points
+        .iter()
+        .enumerate()
+        .filter(|(ndx, _)| sizes[labels[*ndx]] > 1)
+        .map(|(ndx, p)| (ndx, metric.distance(&centroids[labels[ndx]], p)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .ok_or_else(|| KMeansError::new("kmeans-rs: too few points to keep k clusters"))?
+        .0;
+
+    sizes[labels[farthest]] -= 1;
+    sizes[empty] += 1;
+    labels[farthest] = empty;
+    centroids[empty] = points[farthest].clone();
+
+    Ok(())
+}
+
+// split the largest cluster in two: the empty centroid moves onto the member
+// farthest from the largest cluster's centroid, and the members are divided
+// between the two. the farthest and nearest members are pinned to either side
+// so that neither half can come out empty
+fn split_largest(
+    metric: &Metric,
+    points: &[Point],
+    centroids: &mut [Point],
+    labels: &mut [usize],
+    sizes: &mut [usize],
+    empty: usize,
+) -> Result<(), Box<KMeansError>> {
+    let largest = (0..sizes.len()).max_by_key(|ndx| sizes[*ndx]).unwrap();
+    if sizes[largest] < 2 {
+        return Err(KMeansError::new(
+            "kmeans-rs: too few points to keep k clusters",
+        ));
+    }
+
+    let members: Vec<usize> = (0..points.len())
+        .filter(|ndx| labels[*ndx] == largest)
+        .collect();
+    let by_error = |ndx: &usize| metric.distance(&centroids[largest], &points[*ndx]);
+    let farthest = *members
+        .iter()
+        .max_by(|a, b| by_error(a).total_cmp(&by_error(b)))
+        .unwrap();
+    let nearest = *members
+        .iter()
+        .filter(|ndx| **ndx != farthest)
+        .min_by(|a, b| by_error(a).total_cmp(&by_error(b)))
+        .unwrap();
+
+    centroids[empty] = points[farthest].clone();
+    for ndx in members {
+        let to_empty = match ndx {
+            _ if ndx == farthest => true,
+            _ if ndx == nearest => false,
+            _ => {
+                metric.distance(&centroids[empty], &points[ndx])
+                    < metric.distance(&centroids[largest], &points[ndx])
+            }
+        };
+
+        if to_empty {
+            labels[ndx] = empty;
+            sizes[largest] -= 1;
+            sizes[empty] += 1;
+        }
     }
 
-    (next, total_error)
+    Ok(())
 }
 
+mod bisecting;
+mod elkan;
+mod fuzzy;
+mod hamerly;
+mod kdtree;
+mod medoids;
+mod minibatch;
+
 #[cfg(test)]
 mod test;
@@ -1,51 +1,1224 @@
 use super::*;
-use std::collections::HashSet;
-use std::path::PathBuf;
+use crate::distance::Metric;
+use crate::point::generate_clustered_points;
+
+fn test_points<R: Rng + ?Sized>(r: &mut R) -> Vec<Point> {
+    let bounds = (
+        &Point::new(vec![0_f64, 0_f64]),
+        &Point::new(vec![100_f64, 100_f64]),
+    );
+
+    generate_clustered_points(r, bounds, 4, 100)
+}
 
 #[test]
 fn test_calculate_next_centroid() {
-    let prev = Centroid {
-        p: Point { x: 0_f64, y: 0_f64 },
-        color: 5,
-    };
-
-    let points = vec![
-        &Point { x: 0_f64, y: 0_f64 },
-        &Point {
-            x: 20_f64,
-            y: 40_f64,
-        },
+    let points = [
+        Point::new(vec![0_f64, 0_f64]),
+        Point::new(vec![20_f64, 40_f64]),
     ];
 
-    let next = calculate_next_centroid(prev, points);
-    assert_eq!(5, next.color);
-    assert_eq!(10_f64, next.p.x);
-    assert_eq!(20_f64, next.p.y);
+    let next = calculate_next_centroid(
+        &Metric::SquaredEuclidean,
+        &points.iter().collect::<Vec<_>>(),
+    );
+    assert_eq!(vec![10_f64, 20_f64], next.coords);
 }
 
 #[test]
 fn test_init_centroid() {
-    let cfg = &Config {
-        k: 13,
-        num_points: 100,
-        iterations: 10,
-        png_out: PathBuf::from("test"),
-        points_file: None,
-        json_out: true,
-        lower_bound: Point { x: 0_f64, y: 0_f64 },
-        upper_bound: Point {
-            x: 100_f64,
-            y: 100_f64,
-        },
-    };
-    let centroids = init_centroids(cfg);
+    let bounds = (
+        &Point::new(vec![0_f64, 0_f64]),
+        &Point::new(vec![100_f64, 100_f64]),
+    );
+    let centroids = init_centroids(13, bounds, &mut rand::thread_rng());
 
     assert_eq!(13, centroids.len());
-
-    let mut colors_seen = HashSet::new();
     centroids.iter().for_each(|c| {
-        assert!(c.color > 0_usize);
-        assert!(!colors_seen.contains(&c.color));
-        colors_seen.insert(c.color);
+        assert!(c.within(bounds));
     });
 }
+
+#[test]
+fn test_init_centroids_plus_plus() {
+    // three groups of coincident points: once a group holds a centroid its
+    // points weigh nothing, so every seed must pick one centroid per group
+    let groups = [
+        Point::new(vec![1_f64, 1_f64]),
+        Point::new(vec![50_f64, 50_f64]),
+        Point::new(vec![90_f64, 10_f64]),
+    ];
+    let points: Vec<Point> = groups
+        .iter()
+        .flat_map(|group| vec![group.clone(); 4])
+        .collect();
+
+    for seed in 0..100 {
+        let centroids = init_centroids_plus_plus(
+            &Metric::SquaredEuclidean,
+            3,
+            &points,
+            &mut StdRng::seed_from_u64(seed),
+        );
+
+        assert_eq!(3, centroids.len());
+        for group in &groups {
+            assert_eq!(1, centroids.iter().filter(|c| *c == group).count());
+        }
+    }
+}
+
+#[test]
+fn test_init_from_str() {
+    assert_eq!(Init::Random, Init::from_str("random").unwrap());
+    assert_eq!(Init::KMeansPlusPlus, Init::from_str("kmeans++").unwrap());
+    assert_eq!(Init::Forgy, Init::from_str("forgy").unwrap());
+    assert_eq!(
+        Init::RandomPartition,
+        Init::from_str("random-partition").unwrap()
+    );
+    assert!(Init::from_str("foobar").is_err());
+}
+
+#[test]
+fn test_init_centroids_random_partition() {
+    let points = vec![
+        Point::new(vec![1_f64, 1_f64]),
+        Point::new(vec![2_f64, 2_f64]),
+        Point::new(vec![3_f64, 3_f64]),
+        Point::new(vec![4_f64, 4_f64]),
+    ];
+
+    // with k == num_points every partition holds exactly one input point
+    let centroids = init_centroids_random_partition(
+        &Metric::SquaredEuclidean,
+        4,
+        &points,
+        &mut rand::thread_rng(),
+    );
+    assert_eq!(4, centroids.len());
+    points.iter().for_each(|p| assert!(centroids.contains(p)));
+}
+
+#[test]
+fn test_execute_forgy_too_few_unique() {
+    let params = &KMeans::new(2).init(Init::Forgy);
+    let points = vec![
+        Point::new(vec![1_f64, 1_f64]),
+        Point::new(vec![1_f64, 1_f64]),
+        Point::new(vec![1_f64, 1_f64]),
+    ];
+
+    assert!(execute(params, &points, &mut rand::thread_rng()).is_err());
+}
+
+#[test]
+fn test_execute_keeps_lowest_inertia_restart() {
+    let params = &KMeans::new(4).n_init(5).init(Init::KMeansPlusPlus);
+    let points = test_points(&mut rand::thread_rng());
+
+    let result = execute(params, &points, &mut rand::thread_rng()).unwrap();
+    assert_eq!(5, result.restart_inertia.len());
+    assert_eq!(result.inertia, result.restart_inertia[result.best_restart]);
+    assert!(result
+        .restart_inertia
+        .iter()
+        .all(|inertia| result.inertia <= *inertia));
+}
+
+fn empty_cluster_fixture() -> (Vec<Point>, Vec<Point>) {
+    let points = vec![
+        Point::new(vec![1_f64, 1_f64]),
+        Point::new(vec![2_f64, 2_f64]),
+        Point::new(vec![3_f64, 3_f64]),
+        Point::new(vec![10_f64, 10_f64]),
+    ];
+
+    // the third centroid is farther from every point than the other two
+    let centroids = vec![
+        Point::new(vec![2_f64, 2_f64]),
+        Point::new(vec![10_f64, 10_f64]),
+        Point::new(vec![99_f64, 0_f64]),
+    ];
+
+    (points, centroids)
+}
+
+#[test]
+fn test_regroup_points_reseed_farthest() {
+    let (points, centroids) = empty_cluster_fixture();
+
+    let clustering = regroup_points(
+        &points,
+        centroids,
+        EmptyCluster::Farthest,
+        &Metric::SquaredEuclidean,
+    )
+    .unwrap();
+    assert_eq!(3, clustering.centroids.len());
+    assert!(clustering
+        .members(&points)
+        .iter()
+        .all(|members| !members.is_empty()));
+    assert_eq!(2_f64, clustering.inertia);
+}
+
+#[test]
+fn test_regroup_points_split_largest() {
+    let (points, centroids) = empty_cluster_fixture();
+
+    let clustering = regroup_points(
+        &points,
+        centroids,
+        EmptyCluster::Split,
+        &Metric::SquaredEuclidean,
+    )
+    .unwrap();
+    assert_eq!(3, clustering.centroids.len());
+    assert!(clustering
+        .members(&points)
+        .iter()
+        .all(|members| !members.is_empty()));
+}
+
+#[test]
+fn test_regroup_points_empty_cluster_error() {
+    let (points, centroids) = empty_cluster_fixture();
+
+    assert!(regroup_points(
+        &points,
+        centroids,
+        EmptyCluster::Error,
+        &Metric::SquaredEuclidean
+    )
+    .is_err());
+}
+
+#[test]
+fn test_execute_every_iteration_holds_k_clusters() {
+    for policy in [EmptyCluster::Farthest, EmptyCluster::Split] {
+        // random init in wide bounds around tightly packed points reliably
+        // strands some centroids without members
+        let params = &KMeans::new(8)
+            .empty_cluster(policy)
+            .n_init(4)
+            .history(History::Full)
+            .bounds(
+                Point::new(vec![0_f64, 0_f64]),
+                Point::new(vec![100_f64, 100_f64]),
+            );
+        let points: Vec<Point> = (0..16)
+            .map(|ndx| Point::new(vec![1_f64 + (ndx % 4) as f64, 1_f64 + (ndx / 4) as f64]))
+            .collect();
+
+        let result = execute(params, &points, &mut rand::thread_rng()).unwrap();
+        assert_eq!(result.convergence.iterations + 1, result.history.len());
+        result.history.iter().for_each(|snapshot| {
+            assert_eq!(8, snapshot.centroids.len());
+            assert_eq!(points.len(), snapshot.labels.as_ref().unwrap().len());
+            assert!(snapshot
+                .members(&points)
+                .iter()
+                .all(|members| !members.is_empty()));
+        });
+    }
+}
+
+#[test]
+fn test_empty_cluster_from_str() {
+    assert_eq!(
+        EmptyCluster::Farthest,
+        EmptyCluster::from_str("farthest").unwrap()
+    );
+    assert_eq!(
+        EmptyCluster::Split,
+        EmptyCluster::from_str("split").unwrap()
+    );
+    assert_eq!(
+        EmptyCluster::Error,
+        EmptyCluster::from_str("error").unwrap()
+    );
+    assert!(EmptyCluster::from_str("foobar").is_err());
+}
+
+#[test]
+fn test_execute_seeded_is_reproducible() {
+    let params = &KMeans::new(4)
+        .n_init(3)
+        .init(Init::KMeansPlusPlus)
+        .history(History::Full);
+
+    let run = || {
+        let mut r = StdRng::seed_from_u64(7);
+        let points = test_points(&mut r);
+        let result = execute(params, &points, &mut r).unwrap();
+        (points, result.history, result.restart_inertia)
+    };
+
+    let (first_points, first_history, first_inertia) = run();
+    let (second_points, second_history, second_inertia) = run();
+    assert_eq!(first_points, second_points);
+    assert_eq!(first_history, second_history);
+    assert_eq!(first_inertia, second_inertia);
+}
+
+#[test]
+fn test_execute_reports_progress() {
+    let reported = Arc::new(std::sync::Mutex::new(vec![]));
+    let sink = Arc::clone(&reported);
+    let params = &KMeans::new(4).progress(move |progress| sink.lock().unwrap().push(progress));
+
+    let mut r = StdRng::seed_from_u64(7);
+    let points = test_points(&mut r);
+    let result = execute(params, &points, &mut r).unwrap();
+
+    let reported = reported.lock().unwrap();
+    let restart = result.best_restart;
+    let iterations = reported
+        .iter()
+        .filter(|p| matches!(p, Progress::Iteration { restart: r, .. } if *r == restart))
+        .count();
+    assert_eq!(result.convergence.iterations, iterations);
+    assert!(reported.contains(&Progress::Converged {
+        restart,
+        iterations,
+        reason: result.convergence.reason,
+    }));
+}
+
+#[test]
+fn test_fit() {
+    let points = test_points(&mut rand::thread_rng());
+    let model = KMeans::new(4)
+        .init(Init::KMeansPlusPlus)
+        .seed(11)
+        .fit(&points)
+        .unwrap();
+
+    assert_eq!(4, model.centroids.len());
+    assert_eq!(points.len(), model.labels.len());
+    assert!(model.labels.iter().all(|label| *label < 4));
+
+    let total_error: f64 = points
+        .iter()
+        .zip(&model.labels)
+        .map(|(p, label)| model.centroids[*label].sum_squared_error(p))
+        .sum();
+    assert!(f64::abs(total_error - model.inertia) < 1e-6);
+}
+
+#[test]
+fn test_fit_seeded_is_reproducible() {
+    let points = test_points(&mut rand::thread_rng());
+    let params = KMeans::new(4).init(Init::KMeansPlusPlus).seed(3);
+
+    let first = params.fit(&points).unwrap();
+    let second = params.fit(&points).unwrap();
+    assert_eq!(first.centroids, second.centroids);
+    assert_eq!(first.labels, second.labels);
+}
+
+#[test]
+fn test_fit_rejects_k_above_num_points() {
+    let points = vec![Point::new(vec![1_f64, 1_f64])];
+
+    assert!(KMeans::new(2).fit(&points).is_err());
+}
+
+#[test]
+fn test_predict() {
+    let points = vec![
+        Point::new(vec![1_f64, 1_f64]),
+        Point::new(vec![2_f64, 2_f64]),
+        Point::new(vec![50_f64, 50_f64]),
+        Point::new(vec![51_f64, 51_f64]),
+    ];
+    let model = KMeans::new(2)
+        .init(Init::Forgy)
+        .seed(5)
+        .fit(&points)
+        .unwrap();
+
+    assert_eq!(
+        model.labels[0],
+        model.predict(&Point::new(vec![0_f64, 0_f64]))
+    );
+    assert_eq!(
+        model.labels[3],
+        model.predict(&Point::new(vec![60_f64, 60_f64]))
+    );
+}
+
+#[test]
+fn test_clustering_members() {
+    let points = vec![
+        Point::new(vec![1_f64, 1_f64]),
+        Point::new(vec![2_f64, 2_f64]),
+        Point::new(vec![3_f64, 3_f64]),
+    ];
+    let clustering = Clustering {
+        centroids: vec![
+            Point::new(vec![2_f64, 2_f64]),
+            Point::new(vec![1_f64, 1_f64]),
+        ],
+        labels: vec![1, 0, 0],
+        inertia: 1_f64,
+        medoids: None,
+        memberships: None,
+        split_tree: None,
+    };
+
+    let members = clustering.members(&points);
+    assert_eq!(vec![&points[1], &points[2]], members[0]);
+    assert_eq!(vec![&points[0]], members[1]);
+}
+
+#[test]
+fn test_execute_history_modes() {
+    let points = test_points(&mut rand::thread_rng());
+    let run = |history: History| {
+        let params = KMeans::new(4)
+            .init(Init::Forgy)
+            .tolerance(0_f64)
+            .max_iterations(5)
+            .history(history);
+        execute(&params, &points, &mut StdRng::seed_from_u64(9)).unwrap()
+    };
+
+    let full = run(History::Full);
+    assert_eq!(full.convergence.iterations + 1, full.history.len());
+    assert!(full.history.iter().all(|s| s.labels.is_some()));
+
+    // same seed, so the recorded centroids match the full history exactly
+    let centroids = run(History::Centroids);
+    assert_eq!(full.history.len(), centroids.history.len());
+    for (a, b) in full.history.iter().zip(&centroids.history) {
+        assert_eq!(a.iteration, b.iteration);
+        assert_eq!(a.centroids, b.centroids);
+    }
+    let (last, rest) = centroids.history.split_last().unwrap();
+    assert!(rest.iter().all(|s| s.labels.is_none()));
+    assert_eq!(full.clustering.labels, last.labels(&points));
+
+    let last_only = run(History::Final);
+    assert_eq!(1, last_only.history.len());
+    assert_eq!(full.convergence.iterations, last_only.history[0].iteration);
+    assert_eq!(full.clustering, last_only.clustering);
+}
+
+#[test]
+fn test_snapshot_labels_fall_back_to_nearest_centroid() {
+    let points = vec![
+        Point::new(vec![1_f64, 1_f64]),
+        Point::new(vec![9_f64, 9_f64]),
+    ];
+    let snapshot = Snapshot {
+        iteration: 1,
+        centroids: vec![
+            Point::new(vec![10_f64, 10_f64]),
+            Point::new(vec![0_f64, 0_f64]),
+        ],
+        labels: None,
+        inertia: 4_f64,
+        metric: Metric::SquaredEuclidean,
+        medoids: None,
+        memberships: None,
+    };
+
+    assert_eq!(vec![1, 0], snapshot.labels(&points));
+    assert_eq!(vec![&points[1]], snapshot.members(&points)[0]);
+}
+
+#[test]
+fn test_history_from_str() {
+    assert_eq!(History::Full, History::from_str("full").unwrap());
+    assert_eq!(History::Centroids, History::from_str("centroids").unwrap());
+    assert_eq!(History::Final, History::from_str("final").unwrap());
+    assert!(History::from_str("foobar").is_err());
+}
+
+fn two_blobs() -> Vec<Point> {
+    vec![
+        Point::new(vec![1_f64, 1_f64]),
+        Point::new(vec![2_f64, 1_f64]),
+        Point::new(vec![1_f64, 2_f64]),
+        Point::new(vec![50_f64, 50_f64]),
+        Point::new(vec![51_f64, 50_f64]),
+        Point::new(vec![50_f64, 51_f64]),
+    ]
+}
+
+#[test]
+fn test_convergence_inertia_tolerance() {
+    let points = two_blobs();
+    let params = KMeans::new(2).init(Init::KMeansPlusPlus).max_iterations(50);
+
+    let result = execute(&params, &points, &mut StdRng::seed_from_u64(1)).unwrap();
+    assert!(result.convergence.converged);
+    assert_eq!(StopReason::InertiaTolerance, result.convergence.reason);
+    assert!(result.convergence.iterations < 50);
+}
+
+#[test]
+fn test_convergence_max_iterations() {
+    let points = test_points(&mut rand::thread_rng());
+    let params = KMeans::new(4).tolerance(0_f64).max_iterations(1);
+
+    let result = execute(&params, &points, &mut StdRng::seed_from_u64(1)).unwrap();
+    assert!(!result.convergence.converged);
+    assert_eq!(StopReason::MaxIterations, result.convergence.reason);
+    assert_eq!(1, result.convergence.iterations);
+}
+
+#[test]
+fn test_convergence_centroid_shift() {
+    let points = two_blobs();
+    let params = KMeans::new(2)
+        .init(Init::KMeansPlusPlus)
+        .tolerance(0_f64)
+        .max_centroid_shift(1e-9)
+        .max_iterations(50);
+
+    let result = execute(&params, &points, &mut StdRng::seed_from_u64(1)).unwrap();
+    assert!(result.convergence.converged);
+    assert_eq!(StopReason::CentroidShift, result.convergence.reason);
+}
+
+#[test]
+fn test_convergence_labels_unchanged() {
+    let points = two_blobs();
+    let params = KMeans::new(2)
+        .init(Init::KMeansPlusPlus)
+        .tolerance(0_f64)
+        .stop_when_labels_stable(true)
+        .max_iterations(50);
+
+    let result = execute(&params, &points, &mut StdRng::seed_from_u64(1)).unwrap();
+    assert!(result.convergence.converged);
+    assert_eq!(StopReason::LabelsUnchanged, result.convergence.reason);
+}
+
+#[test]
+fn test_stop_reason_relative_tolerance() {
+    let prev = Clustering {
+        centroids: vec![Point::new(vec![0_f64, 0_f64])],
+        labels: vec![0],
+        inertia: 1_000_000_f64,
+        medoids: None,
+        memberships: None,
+        split_tree: None,
+    };
+    let next = Clustering {
+        inertia: 999_990_f64,
+        ..prev.clone()
+    };
+
+    // an absolute change of 10 is far above 0.01, but only 1e-5 relative to the total
+    let absolute = KMeans::new(1).tolerance(0.01);
+    assert_eq!(None, absolute.stop_reason(&prev, &next));
+
+    let relative = KMeans::new(1)
+        .tolerance(1e-4)
+        .tolerance_mode(ToleranceMode::Relative);
+    assert_eq!(
+        Some(StopReason::InertiaTolerance),
+        relative.stop_reason(&prev, &next)
+    );
+}
+
+#[test]
+fn test_tolerance_mode_from_str() {
+    assert_eq!(
+        ToleranceMode::Absolute,
+        ToleranceMode::from_str("absolute").unwrap()
+    );
+    assert_eq!(
+        ToleranceMode::Relative,
+        ToleranceMode::from_str("relative").unwrap()
+    );
+    assert!(ToleranceMode::from_str("foobar").is_err());
+}
+
+#[test]
+fn test_execute_identical_across_thread_counts() {
+    let points = test_points(&mut StdRng::seed_from_u64(21));
+    let run = |threads: usize| {
+        let params = KMeans::new(4)
+            .init(Init::KMeansPlusPlus)
+            .n_init(3)
+            .history(History::Full)
+            .threads(threads);
+        execute(&params, &points, &mut StdRng::seed_from_u64(5)).unwrap()
+    };
+
+    let serial = run(1);
+    let parallel = run(4);
+    assert_eq!(serial.clustering, parallel.clustering);
+    assert_eq!(serial.history, parallel.history);
+    assert_eq!(serial.restart_inertia, parallel.restart_inertia);
+}
+
+#[test]
+fn test_execute_rejects_zero_threads() {
+    let points = test_points(&mut rand::thread_rng());
+
+    assert!(KMeans::new(4).threads(0).fit(&points).is_err());
+}
+
+#[test]
+fn test_fit_high_dimensional_points() {
+    let mut r = StdRng::seed_from_u64(8);
+    let bounds = (&Point::new(vec![0_f64; 16]), &Point::new(vec![100_f64; 16]));
+    let points = generate_clustered_points(&mut r, bounds, 3, 150);
+
+    let model = KMeans::new(3)
+        .init(Init::KMeansPlusPlus)
+        .n_init(3)
+        .seed(8)
+        .fit(&points)
+        .unwrap();
+
+    assert_eq!(3, model.centroids.len());
+    assert!(model.centroids.iter().all(|c| c.dim() == 16));
+    assert_eq!(model.labels[0], model.predict(&points[0]));
+}
+
+#[test]
+fn test_fit_rejects_mixed_dimensions() {
+    let points = vec![
+        Point::new(vec![1_f64, 1_f64]),
+        Point::new(vec![2_f64, 2_f64, 2_f64]),
+    ];
+
+    assert!(KMeans::new(1).fit(&points).is_err());
+}
+
+#[test]
+fn test_fit_rejects_bounds_dimension_mismatch() {
+    let points = test_points(&mut rand::thread_rng());
+    let params = KMeans::new(4).bounds(
+        Point::new(vec![0_f64, 0_f64, 0_f64]),
+        Point::new(vec![100_f64, 100_f64, 100_f64]),
+    );
+
+    assert!(params.fit(&points).is_err());
+}
+
+#[test]
+fn test_fit_rejects_empty_bounds() {
+    let points = test_points(&mut rand::thread_rng());
+    let bounds = [
+        (vec![0_f64, 0_f64], vec![100_f64, 0_f64]),
+        (vec![0_f64, 100_f64], vec![100_f64, 0_f64]),
+        (vec![0_f64, f64::NAN], vec![100_f64, 100_f64]),
+        (vec![0_f64, 0_f64], vec![f64::INFINITY, 100_f64]),
+    ];
+
+    for (lower, upper) in bounds {
+        let params = KMeans::new(4).bounds(Point::new(lower), Point::new(upper));
+        assert!(params.fit(&points).is_err());
+    }
+
+    let params = KMeans::new(4).bounds(
+        Point::new(vec![0_f64, 0_f64]),
+        Point::new(vec![100_f64, 100_f64]),
+    );
+    assert!(params.fit(&points).is_ok());
+}
+
+#[test]
+fn test_fit_with_each_metric() {
+    let points = test_points(&mut StdRng::seed_from_u64(13));
+    let metrics = [
+        Metric::SquaredEuclidean,
+        Metric::Manhattan,
+        Metric::Chebyshev,
+        Metric::Cosine,
+        Metric::Minkowski(3_f64),
+    ];
+
+    for metric in metrics {
+        let model = KMeans::new(4)
+            .init(Init::KMeansPlusPlus)
+            .metric(metric)
+            .seed(13)
+            .fit(&points)
+            .unwrap();
+
+        assert_eq!(metric, model.metric);
+        assert_eq!(4, model.centroids.len());
+
+        // every point is labelled with its nearest centroid under the metric
+        points.iter().zip(&model.labels).for_each(|(p, label)| {
+            assert_eq!(*label, model.predict(p));
+        });
+
+        let inertia: f64 = points
+            .iter()
+            .zip(&model.labels)
+            .map(|(p, label)| metric.distance(&model.centroids[*label], p))
+            .sum();
+        assert!((inertia - model.inertia).abs() < 1e-6 * f64::max(1_f64, inertia));
+    }
+}
+
+#[test]
+fn test_manhattan_update_uses_median() {
+    let points = [
+        Point::new(vec![0_f64, 0_f64]),
+        Point::new(vec![1_f64, 2_f64]),
+        Point::new(vec![100_f64, 4_f64]),
+    ];
+
+    let next = calculate_next_centroid(&Metric::Manhattan, &points.iter().collect::<Vec<_>>());
+    assert_eq!(vec![1_f64, 2_f64], next.coords);
+}
+
+#[test]
+fn test_validate_rejects_invalid_minkowski_order() {
+    let points = test_points(&mut rand::thread_rng());
+
+    for p in [0.5, f64::INFINITY, f64::NAN] {
+        assert!(KMeans::new(4)
+            .metric(Metric::Minkowski(p))
+            .fit(&points)
+            .is_err());
+    }
+}
+
+#[test]
+fn test_accelerated_algorithms_match_lloyd() {
+    let bounds = (
+        &Point::new(vec![0_f64, 0_f64, 0_f64]),
+        &Point::new(vec![100_f64, 100_f64, 100_f64]),
+    );
+    let points = generate_clustered_points(&mut StdRng::seed_from_u64(17), bounds, 12, 600);
+    let metrics = [
+        Metric::SquaredEuclidean,
+        Metric::Manhattan,
+        Metric::Chebyshev,
+        Metric::Minkowski(3_f64),
+    ];
+
+    for (metric, init) in metrics
+        .iter()
+        .zip([Init::Random, Init::KMeansPlusPlus].iter().cycle())
+    {
+        let run = |algorithm: Algorithm| {
+            let params = KMeans::new(20)
+                .metric(*metric)
+                .init(*init)
+                .algorithm(algorithm)
+                .max_iterations(30)
+                .history(History::Full);
+            execute(&params, &points, &mut StdRng::seed_from_u64(3)).unwrap()
+        };
+
+        let lloyd = run(Algorithm::Lloyd);
+        assert_eq!(0, lloyd.distance_evaluations.skipped);
+
+        for algorithm in [Algorithm::Elkan, Algorithm::Hamerly, Algorithm::KdTree] {
+            let accelerated = run(algorithm);
+            assert_eq!(lloyd.history, accelerated.history);
+            assert_eq!(lloyd.clustering.labels, accelerated.clustering.labels);
+            assert_eq!(lloyd.convergence, accelerated.convergence);
+            assert!(accelerated.distance_evaluations.skipped > 0);
+            assert!(
+                accelerated.distance_evaluations.computed < lloyd.distance_evaluations.computed
+            );
+        }
+    }
+}
+
+#[test]
+fn test_accelerated_algorithms_match_lloyd_with_ties_and_empty_clusters() {
+    // duplicated points tie between centroids, and random init in a wide
+    // box leaves clusters empty for the policies to repair
+    let mut points = test_points(&mut StdRng::seed_from_u64(29));
+    points.extend(points.clone());
+
+    for policy in [EmptyCluster::Farthest, EmptyCluster::Split] {
+        let run = |algorithm: Algorithm| {
+            let params = KMeans::new(10)
+                .empty_cluster(policy)
+                .bounds(
+                    Point::new(vec![-1000_f64, -1000_f64]),
+                    Point::new(vec![1000_f64, 1000_f64]),
+                )
+                .algorithm(algorithm)
+                .history(History::Full);
+            execute(&params, &points, &mut StdRng::seed_from_u64(7)).unwrap()
+        };
+
+        let lloyd = run(Algorithm::Lloyd);
+        assert_eq!(lloyd.history, run(Algorithm::Elkan).history);
+        assert_eq!(lloyd.history, run(Algorithm::Hamerly).history);
+        assert_eq!(lloyd.history, run(Algorithm::KdTree).history);
+    }
+}
+
+#[test]
+fn test_kdtree_matches_lloyd_on_grid() {
+    // integer coordinates put points exactly on cell boundaries and
+    // equidistant from centroids, and repeat whole columns of the tree
+    let points: Vec<Point> = (0..4000)
+        .map(|ndx| Point::new(vec![(ndx % 50) as f64, (ndx / 50 % 40) as f64]))
+        .collect();
+
+    for metric in [
+        Metric::SquaredEuclidean,
+        Metric::Manhattan,
+        Metric::Chebyshev,
+    ] {
+        let run = |algorithm: Algorithm| {
+            let params = KMeans::new(24)
+                .metric(metric)
+                .init(Init::Forgy)
+                .algorithm(algorithm)
+                .max_iterations(20)
+                .history(History::Full);
+            execute(&params, &points, &mut StdRng::seed_from_u64(43)).unwrap()
+        };
+
+        let lloyd = run(Algorithm::Lloyd);
+        let kdtree = run(Algorithm::KdTree);
+        assert_eq!(lloyd.history, kdtree.history);
+        assert!(kdtree.distance_evaluations.skipped > kdtree.distance_evaluations.computed);
+    }
+}
+
+#[test]
+fn test_hamerly_matches_lloyd_in_low_dimensions() {
+    let points = test_points(&mut StdRng::seed_from_u64(31));
+
+    for seed in 0..5 {
+        let run = |algorithm: Algorithm| {
+            KMeans::new(6)
+                .init(Init::KMeansPlusPlus)
+                .n_init(2)
+                .algorithm(algorithm)
+                .seed(seed)
+                .fit(&points)
+                .unwrap()
+        };
+
+        let lloyd = run(Algorithm::Lloyd);
+        let hamerly = run(Algorithm::Hamerly);
+        assert_eq!(lloyd.labels, hamerly.labels);
+        assert_eq!(lloyd.centroids, hamerly.centroids);
+        assert_eq!(lloyd.inertia, hamerly.inertia);
+    }
+}
+
+#[test]
+fn test_accelerated_algorithms_reject_cosine() {
+    let points = test_points(&mut rand::thread_rng());
+
+    for algorithm in [Algorithm::Elkan, Algorithm::Hamerly, Algorithm::KdTree] {
+        let params = KMeans::new(4).metric(Metric::Cosine).algorithm(algorithm);
+        assert!(params.fit(&points).is_err());
+    }
+}
+
+#[test]
+fn test_algorithm_from_str() {
+    assert_eq!(Algorithm::Lloyd, Algorithm::from_str("lloyd").unwrap());
+    assert_eq!(Algorithm::Elkan, Algorithm::from_str("elkan").unwrap());
+    assert_eq!(Algorithm::Hamerly, Algorithm::from_str("hamerly").unwrap());
+    assert_eq!(Algorithm::KdTree, Algorithm::from_str("kdtree").unwrap());
+    assert!(Algorithm::from_str("foobar").is_err());
+}
+
+#[test]
+fn test_minibatch_approaches_lloyd_inertia() {
+    let bounds = (
+        &Point::new(vec![0_f64, 0_f64]),
+        &Point::new(vec![1000_f64, 1000_f64]),
+    );
+    let points = generate_clustered_points(&mut StdRng::seed_from_u64(37), bounds, 5, 5000);
+    let run = |algorithm: Algorithm| {
+        let params = KMeans::new(5)
+            .init(Init::KMeansPlusPlus)
+            .algorithm(algorithm)
+            .batch_size(256)
+            .max_iterations(500)
+            .history(History::Full);
+        execute(&params, &points, &mut StdRng::seed_from_u64(2)).unwrap()
+    };
+
+    let lloyd = run(Algorithm::Lloyd);
+    let minibatch = run(Algorithm::MiniBatch);
+    // a few percent worse than Lloyd is expected of mini-batch
+    assert!(minibatch.inertia < lloyd.inertia * 1.1);
+
+    // the final state is a full assignment, intermediate states carry none
+    let last = minibatch.history.last().unwrap();
+    assert_eq!(points.len(), last.labels.as_ref().unwrap().len());
+    assert_eq!(minibatch.inertia, last.inertia);
+    assert!(minibatch.history[..minibatch.history.len() - 1]
+        .iter()
+        .all(|s| s.labels.is_none()));
+    assert!(minibatch.distance_evaluations.computed < lloyd.distance_evaluations.computed);
+}
+
+#[test]
+fn test_minibatch_stops_when_smoothed_inertia_stalls() {
+    let points = test_points(&mut StdRng::seed_from_u64(41));
+    let params = KMeans::new(4)
+        .algorithm(Algorithm::MiniBatch)
+        .batch_size(20)
+        .max_no_improvement(5)
+        .max_iterations(10_000);
+
+    let result = execute(&params, &points, &mut StdRng::seed_from_u64(1)).unwrap();
+    assert!(result.convergence.converged);
+    assert_eq!(StopReason::NoImprovement, result.convergence.reason);
+    assert!(result.convergence.iterations < 10_000);
+}
+
+#[test]
+fn test_minibatch_seeded_reproducibility() {
+    let points = test_points(&mut StdRng::seed_from_u64(43));
+    let run = || {
+        KMeans::new(4)
+            .algorithm(Algorithm::MiniBatch)
+            .batch_size(32)
+            .n_init(2)
+            .seed(9)
+            .fit(&points)
+            .unwrap()
+    };
+
+    let (a, b) = (run(), run());
+    assert_eq!(a.labels, b.labels);
+    assert_eq!(a.centroids, b.centroids);
+    assert_eq!(a.inertia, b.inertia);
+}
+
+#[test]
+fn test_minibatch_validation() {
+    let points = test_points(&mut rand::thread_rng());
+    let params = KMeans::new(4).algorithm(Algorithm::MiniBatch);
+
+    assert!(params.clone().batch_size(0).fit(&points).is_err());
+    assert!(params.metric(Metric::Manhattan).fit(&points).is_err());
+}
+
+#[test]
+fn test_medoids_from_str() {
+    assert_eq!(
+        Medoids::Alternating,
+        Medoids::from_str("alternating").unwrap()
+    );
+    assert_eq!(Medoids::Pam, Medoids::from_str("pam").unwrap());
+    assert!(Medoids::from_str("foobar").is_err());
+}
+
+#[test]
+fn test_medoids_are_input_points() {
+    let points = test_points(&mut StdRng::seed_from_u64(47));
+
+    for metric in [Metric::SquaredEuclidean, Metric::Manhattan, Metric::Cosine] {
+        for method in [Medoids::Alternating, Medoids::Pam] {
+            let params = KMeans::new(4)
+                .metric(metric)
+                .medoids(method)
+                .init(Init::KMeansPlusPlus)
+                .history(History::Full);
+            let result = execute(&params, &points, &mut StdRng::seed_from_u64(5)).unwrap();
+
+            for snapshot in &result.history {
+                let medoids = snapshot.medoids.as_ref().unwrap();
+                for (centroid, ndx) in snapshot.centroids.iter().zip(medoids) {
+                    assert_eq!(points[*ndx].coords, centroid.coords);
+                }
+            }
+
+            // every point sits with its nearest medoid
+            let clustering = &result.clustering;
+            for (point, label) in points.iter().zip(&clustering.labels) {
+                let (_, nearest) = nearest_centroid(&metric, &clustering.centroids, point);
+                assert_eq!(
+                    nearest,
+                    metric.distance(&clustering.centroids[*label], point)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_alternating_update_picks_member_with_least_total_distance() {
+    let points = [
+        Point::new(vec![0_f64]),
+        Point::new(vec![1_f64]),
+        Point::new(vec![2_f64]),
+        Point::new(vec![10_f64]),
+        Point::new(vec![50_f64]),
+    ];
+    let clustering = Clustering {
+        centroids: vec![points[0].clone(), points[4].clone()],
+        labels: vec![0, 0, 0, 0, 1],
+        inertia: 0_f64,
+        medoids: Some(vec![0, 4]),
+        memberships: None,
+        split_tree: None,
+    };
+
+    // 1 and 2 both total 11 under manhattan distance, the lower index wins
+    assert_eq!(
+        vec![1, 4],
+        medoids::update(&Metric::Manhattan, &points, &clustering)
+    );
+}
+
+#[test]
+fn test_pam_finds_optimal_medoids() {
+    let bounds = (
+        &Point::new(vec![0_f64, 0_f64]),
+        &Point::new(vec![100_f64, 100_f64]),
+    );
+    let points = generate_clustered_points(&mut StdRng::seed_from_u64(53), bounds, 3, 14);
+    let cost = |medoids: &[usize]| {
+        let centroids: Vec<Point> = medoids.iter().map(|m| points[*m].clone()).collect();
+        points
+            .iter()
+            .map(|p| nearest_centroid(&Metric::Manhattan, &centroids, p).1)
+            .sum::<f64>()
+    };
+
+    let mut optimum = f64::MAX;
+    for a in 0..points.len() {
+        for b in a + 1..points.len() {
+            for c in b + 1..points.len() {
+                optimum = f64::min(optimum, cost(&[a, b, c]));
+            }
+        }
+    }
+
+    let model = KMeans::new(3)
+        .metric(Metric::Manhattan)
+        .medoids(Medoids::Pam)
+        .tolerance(0_f64)
+        .fit(&points)
+        .unwrap();
+    assert!((model.inertia - optimum).abs() < 1e-9);
+    assert_eq!(StopReason::NoImprovement, model.convergence.reason);
+    assert_eq!(3, model.medoids.unwrap().len());
+}
+
+#[test]
+fn test_medoids_validation() {
+    let points = test_points(&mut rand::thread_rng());
+
+    let random_init = KMeans::new(4).medoids(Medoids::Alternating);
+    assert!(random_init.fit(&points).is_err());
+
+    let accelerated = KMeans::new(4)
+        .medoids(Medoids::Pam)
+        .algorithm(Algorithm::Elkan);
+    assert!(accelerated.fit(&points).is_err());
+
+    // PAM picks its own initial medoids
+    assert!(KMeans::new(4).medoids(Medoids::Pam).fit(&points).is_ok());
+}
+
+#[test]
+fn test_fuzzy_membership() {
+    // with a fuzzifier of 2, memberships are inversely proportional to distance
+    let membership = fuzzy::membership(&[1_f64, 3_f64], 1_f64);
+    assert!((membership[0] - 0.75).abs() < 1e-12);
+    assert!((membership[1] - 0.25).abs() < 1e-12);
+
+    // a point on top of centroids belongs to them alone
+    assert_eq!(
+        vec![0.5, 0_f64, 0.5],
+        fuzzy::membership(&[0_f64, 4_f64, 0_f64], 1_f64)
+    );
+}
+
+#[test]
+fn test_fuzzy_memberships_sum_to_one() {
+    let points = test_points(&mut StdRng::seed_from_u64(59));
+    let params = KMeans::new(4)
+        .fuzzy(2_f64)
+        .init(Init::KMeansPlusPlus)
+        .history(History::Full);
+    let result = execute(&params, &points, &mut StdRng::seed_from_u64(6)).unwrap();
+
+    let clustering = &result.clustering;
+    let memberships = clustering.memberships.as_ref().unwrap();
+    for ((point, label), membership) in points.iter().zip(&clustering.labels).zip(memberships) {
+        assert_eq!(4, membership.len());
+        assert!((membership.iter().sum::<f64>() - 1_f64).abs() < 1e-9);
+
+        // the largest membership is the nearest centroid's
+        let nearest = nearest_centroid(&Metric::SquaredEuclidean, &clustering.centroids, point);
+        assert_eq!(nearest.0, *label);
+    }
+
+    // the fuzzy objective never increases
+    for (prev, next) in result.history.iter().zip(&result.history[1..]) {
+        assert!(next.inertia <= prev.inertia * (1_f64 + 1e-9));
+        assert!(next.memberships.is_some());
+    }
+}
+
+#[test]
+fn test_fuzzy_shows_ambiguous_points() {
+    let mut points = two_blobs();
+    points.push(Point::new(vec![25.5_f64, 25.5_f64]));
+
+    let model = KMeans::new(2)
+        .fuzzy(2_f64)
+        .init(Init::KMeansPlusPlus)
+        .seed(3)
+        .fit(&points)
+        .unwrap();
+    let memberships = model.memberships.unwrap();
+
+    // blob members belong almost entirely to one cluster, the point midway
+    // between them to both alike
+    for membership in &memberships[..6] {
+        assert!(membership.iter().any(|u| *u > 0.99));
+    }
+    assert!(memberships[6].iter().all(|u| (u - 0.5).abs() < 0.05));
+}
+
+#[test]
+fn test_fuzzy_approaches_hard_kmeans() {
+    let points = test_points(&mut StdRng::seed_from_u64(61));
+    let run = |params: KMeans| execute(&params, &points, &mut StdRng::seed_from_u64(8)).unwrap();
+    let params = KMeans::new(3)
+        .init(Init::KMeansPlusPlus)
+        .tolerance(1e-9)
+        .max_iterations(100);
+
+    let lloyd = run(params.clone());
+    let fuzzy = run(params.fuzzy(1.01));
+    assert_eq!(lloyd.clustering.labels, fuzzy.clustering.labels);
+    for (a, b) in lloyd
+        .clustering
+        .centroids
+        .iter()
+        .zip(&fuzzy.clustering.centroids)
+    {
+        assert!(a.sum_squared_error(b).sqrt() < 0.5);
+    }
+}
+
+#[test]
+fn test_fuzzy_validation() {
+    let points = test_points(&mut rand::thread_rng());
+
+    for fuzzifier in [1_f64, 0.5, f64::NAN, f64::INFINITY] {
+        assert!(KMeans::new(4).fuzzy(fuzzifier).fit(&points).is_err());
+    }
+
+    let manhattan = KMeans::new(4).fuzzy(2_f64).metric(Metric::Manhattan);
+    assert!(manhattan.fit(&points).is_err());
+
+    let kdtree = KMeans::new(4).fuzzy(2_f64).algorithm(Algorithm::KdTree);
+    assert!(kdtree.fit(&points).is_err());
+
+    let medoids = KMeans::new(4).fuzzy(2_f64).medoids(Medoids::Pam);
+    assert!(medoids.fit(&points).is_err());
+}
+
+#[test]
+fn test_bisecting_split_tree() {
+    let points = test_points(&mut StdRng::seed_from_u64(67));
+    let params = KMeans::new(5)
+        .bisecting(true)
+        .init(Init::KMeansPlusPlus)
+        .history(History::Full);
+    let result = execute(&params, &points, &mut StdRng::seed_from_u64(9)).unwrap();
+    let clustering = &result.clustering;
+    let tree = clustering.split_tree.as_ref().unwrap();
+
+    assert_eq!(StopReason::ReachedK, result.convergence.reason);
+    assert_eq!(4, result.convergence.iterations);
+    assert_eq!(9, tree.nodes.len());
+    assert_eq!(points.len(), tree.nodes[0].size);
+
+    // children divide their parent's points between them
+    for node in &tree.nodes {
+        if let Some([a, b]) = node.children {
+            assert_eq!(node.size, tree.nodes[a].size + tree.nodes[b].size);
+            assert!(tree.nodes[a].inertia + tree.nodes[b].inertia <= node.inertia);
+        }
+    }
+
+    // the final clusters are the leaves
+    let leaves = tree.cut(5);
+    let leaf_inertia: f64 = leaves.iter().map(|ndx| tree.nodes[*ndx].inertia).sum();
+    assert!((leaf_inertia - clustering.inertia).abs() < 1e-6 * clustering.inertia);
+    for (label, ndx) in leaves.iter().enumerate() {
+        assert!(tree.nodes[*ndx].children.is_none());
+        assert_eq!(clustering.centroids[label], tree.nodes[*ndx].centroid);
+    }
+
+    // cutting the tree back to k clusters reproduces the state after k - 1
+    // splits, and the cluster split next had the largest inertia
+    for (splits, snapshot) in result.history.iter().enumerate() {
+        let k = splits + 1;
+        assert_eq!(k, snapshot.centroids.len());
+        assert_eq!(k, tree.cut(k).len());
+        assert_eq!(
+            snapshot.labels(&points),
+            tree.cut_labels(k, &clustering.labels)
+        );
+
+        if k < 5 {
+            let cut = tree.cut(k);
+            let next = cut
+                .iter()
+                .find(|ndx| tree.nodes[**ndx].split == Some(k))
+                .unwrap();
+            assert!(cut
+                .iter()
+                .all(|ndx| tree.nodes[*ndx].inertia <= tree.nodes[*next].inertia));
+        }
+    }
+}
+
+#[test]
+fn test_bisecting_single_cluster() {
+    let points = two_blobs();
+    let model = KMeans::new(1).bisecting(true).seed(1).fit(&points).unwrap();
+    let tree = model.split_tree.unwrap();
+
+    assert_eq!(1, tree.nodes.len());
+    assert_eq!(vec![0], tree.cut(1));
+    assert_eq!(vec![0; 6], model.labels);
+}
+
+#[test]
+fn test_bisecting_separates_blobs() {
+    let points = two_blobs();
+    let model = KMeans::new(2)
+        .bisecting(true)
+        .init(Init::Forgy)
+        .seed(5)
+        .fit(&points)
+        .unwrap();
+
+    assert_eq!(model.labels[0], model.labels[2]);
+    assert_ne!(model.labels[0], model.labels[3]);
+    assert_eq!(model.labels[3], model.labels[5]);
+    assert!((model.inertia - 8_f64 / 3_f64).abs() < 1e-9);
+}
+
+#[test]
+fn test_bisecting_validation() {
+    let points = test_points(&mut rand::thread_rng());
+
+    let accelerated = KMeans::new(4).bisecting(true).algorithm(Algorithm::Hamerly);
+    assert!(accelerated.fit(&points).is_err());
+
+    let medoids = KMeans::new(4).bisecting(true).medoids(Medoids::Pam);
+    assert!(medoids.fit(&points).is_err());
+
+    let fuzzy = KMeans::new(4).bisecting(true).fuzzy(2_f64);
+    assert!(fuzzy.fit(&points).is_err());
+}
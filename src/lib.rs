@@ -0,0 +1,24 @@
+//! K-means clustering of N-dimensional points.
+//!
+//! Every [`Point`] in a dataset must have the same number of coordinates.
+//!
+//! Configure a run with the [`KMeans`] builder and call [`KMeans::fit`] to get
+//! back a [`KMeansModel`] holding the centroids, per-point labels and inertia.
+//! [`kmeans::execute`] exposes every iteration of a run for callers that want
+//! to render or inspect the intermediate clusterings. A [`GaussianMixture`] can
+//! then be fitted starting from a k-means clustering, and [`evaluation`]
+//! scores how well a clustering fits its input or agrees with known labels.
+
+pub mod distance;
+pub mod evaluation;
+pub mod gmm;
+pub mod kmeans;
+pub mod point;
+
+pub use distance::{Distance, Metric};
+pub use gmm::{Covariance, GaussianMixture, GaussianMixtureModel};
+pub use kmeans::{
+    Algorithm, Clustering, Convergence, DistanceEvaluations, EmptyCluster, History, Init, KMeans,
+    KMeansError, KMeansModel, Medoids, Progress, SplitNode, SplitTree, StopReason, ToleranceMode,
+};
+pub use point::Point;
@@ -1,35 +1,209 @@
+mod bench;
 mod cli;
-mod kmeans;
-mod point;
 mod render;
+mod sweep;
 
 use clap::Parser;
+use kmeans_rs::evaluation::{agreement, silhouette_sampled, Index};
+use kmeans_rs::kmeans;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
 fn main() {
-    // parse and validate CLI args
-    let cfg = cli::Config::parse();
+    // parse and validate CLI args, pinning the seed so it can be reported
+    let mut cfg = cli::Config::parse();
+    let seed = *cfg.seed.get_or_insert_with(rand::random);
+    let dim = cfg.resolve_dim();
     if let Err(e) = cfg.validate() {
         panic!("{}", e);
     }
 
-    // generate or load 2D input points from file
-    let points = cfg.points();
+    // bound every parallel step, clustering and rendering alike
+    if let Some(threads) = cfg.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .unwrap();
+    }
+
+    // a single seeded RNG drives point generation and centroid initialization
+    let mut r = StdRng::seed_from_u64(seed);
+
+    // benchmark the configured algorithms instead of a single clustering
+    if let Some(cli::Command::Bench(bench)) = &cfg.command {
+        eprintln!(
+            "kmeans-rs: generating {} points for the benchmark",
+            bench.num_points
+        );
+        let runs = bench::run(&cfg, bench, &mut r);
+        if let Err(e) = runs {
+            panic!("{}", e);
+        }
+        let runs = runs.unwrap();
+        for run in &runs {
+            eprintln!(
+                "kmeans-rs: {} finished in {:.3}s",
+                run.algorithm, run.seconds
+            );
+        }
+
+        match cfg.json_out {
+            true => println!("{}", render::json_bench(&cfg, &runs).unwrap()),
+            false => print!("{}", render::table_bench(&runs)),
+        }
+        return;
+    }
+
+    // generate or load input points from file, along with any true labels
+    let points = cfg.labelled_points(&mut r);
     if let Err(e) = points {
         panic!("{}", e);
     }
-    let points = points.unwrap();
+    let (points, truth) = points.unwrap();
+    if cfg.ground_truth && truth.is_none() {
+        panic!("kmeans-rs: --ground-truth needs a 'label' on every input point");
+    }
+
+    // cluster with every k in a range instead of the configured one
+    if let Some(cli::Command::Sweep(sweep)) = &cfg.command {
+        let result = sweep::run(&cfg, sweep, &points, &mut r);
+        if let Err(e) = result {
+            panic!("{}", e);
+        }
+        let result = result.unwrap();
+
+        match cfg.json_out {
+            true => println!("{}", render::json_sweep(&cfg, &result).unwrap()),
+            false => print!("{}", render::table_sweep(&result)),
+        }
+
+        let _ = std::fs::remove_dir_all(&cfg.png_out);
+        std::fs::create_dir_all(&cfg.png_out).unwrap();
+        render::png_sweep(&cfg, &result).unwrap();
+        return;
+    }
 
     // run K-means on the inputs
-    let result = kmeans::execute(&cfg, &points);
+    let result = kmeans::execute(&cfg.kmeans(), &points, &mut r);
+    if let Err(e) = result {
+        panic!("{}", e);
+    }
+    let result = result.unwrap();
+
+    // fit a Gaussian mixture starting from the clustering, if asked for
+    let gmm = cfg
+        .gmm()
+        .map(|gmm| gmm.fit(&points, &result.clustering))
+        .transpose();
+    if let Err(e) = gmm {
+        panic!("{}", e);
+    }
+    let gmm = gmm.unwrap();
+    if let Some(gmm) = &gmm {
+        match gmm.converged {
+            true => eprintln!("kmeans-rs: gmm converged at iteration {}", gmm.iterations),
+            false => eprintln!("kmeans-rs: gmm stopped after {} iterations", gmm.iterations),
+        }
+    }
+
+    // score the final clustering, sampling points when there are many
+    let silhouette = match cfg.silhouette {
+        true => {
+            eprintln!("kmeans-rs: scoring silhouettes");
+            Some(silhouette_sampled(
+                &result.metric,
+                &points,
+                &result.clustering.labels,
+                cfg.silhouette_sample,
+                &mut r,
+            ))
+        }
+        false => None,
+    };
+
+    // every validity index of the final clustering, reusing the silhouette
+    // score if it was computed already
+    let metrics: Option<Vec<(Index, Option<f64>)>> = match cfg.metrics {
+        true => {
+            eprintln!("kmeans-rs: computing validity indices");
+            let non_empty = result
+                .clustering
+                .members(&points)
+                .iter()
+                .filter(|cluster| !cluster.is_empty())
+                .count();
+            let score = |index: Index, r: &mut StdRng| match (index, &silhouette) {
+                (Index::Silhouette, Some(silhouette)) if non_empty > 1 => Some(silhouette.score),
+                _ => index.score(
+                    &result.metric,
+                    &points,
+                    &result.clustering,
+                    cfg.silhouette_sample,
+                    r,
+                ),
+            };
+            Some(
+                Index::ALL
+                    .iter()
+                    .map(|index| (*index, score(*index, &mut r)))
+                    .collect(),
+            )
+        }
+        false => None,
+    };
+
+    // compare the final clustering with the true labels, if asked for
+    let agreement = match (cfg.ground_truth, &truth) {
+        (true, Some(truth)) => Some(agreement(truth, &result.clustering.labels)),
+        _ => None,
+    };
 
     // render outputs depending on CLI args
     eprintln!("kmeans-rs: rendering output");
     if cfg.json_out {
-        let output = render::json_all_iterations(&result).unwrap();
+        let output = render::json_all_iterations(
+            &cfg,
+            &points,
+            &result,
+            silhouette.as_ref(),
+            metrics.as_deref(),
+            agreement.as_ref(),
+            gmm.as_ref(),
+        )
+        .unwrap();
         println!("{}", output);
+    } else {
+        if let Some(metrics) = &metrics {
+            print!("{}", render::table_metrics(&result, metrics));
+        }
+        if let Some(agreement) = &agreement {
+            print!("{}", render::table_agreement(agreement));
+        }
+        if let Some(gmm) = &gmm {
+            print!("{}", render::table_gmm(gmm));
+        }
     }
 
-    let _ = std::fs::remove_dir_all(&cfg.png_out);
-    std::fs::create_dir_all(&cfg.png_out).unwrap();
-    render::png_all_iterations(&cfg, &result).unwrap();
+    // the silhouette plot is the only PNG output that does not need 2D points
+    if dim == 2 || silhouette.is_some() {
+        let _ = std::fs::remove_dir_all(&cfg.png_out);
+        std::fs::create_dir_all(&cfg.png_out).unwrap();
+    }
+
+    if let Some(silhouette) = &silhouette {
+        render::png_silhouette(&cfg, silhouette, &result.clustering.labels).unwrap();
+    }
+
+    if dim != 2 {
+        eprintln!(
+            "kmeans-rs: skipping PNG output for {}-dimensional points",
+            dim
+        );
+        return;
+    }
+
+    render::png_all_iterations(&cfg, &points, &result.history).unwrap();
+    if let Some(gmm) = &gmm {
+        render::png_gmm(&cfg, &points, gmm).unwrap();
+    }
 }
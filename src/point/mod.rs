@@ -1,171 +1,226 @@
 use rand::prelude::*;
 use serde::Serialize;
-use std::collections::HashMap;
 use std::f64::consts;
-use std::hash::{Hash, Hasher};
-use std::mem::transmute;
 use std::num::ParseFloatError;
 use std::str::FromStr;
 
-fn generate_points(bounds: (&Point, &Point), cardinality: usize) -> Vec<Point> {
+fn generate_points<R: Rng + ?Sized>(
+    r: &mut R,
+    bounds: (&Point, &Point),
+    cardinality: usize,
+) -> Vec<Point> {
     let mut points = vec![];
     for _ in 1..=cardinality {
-        points.push(generate_point(bounds));
+        points.push(generate_point(r, bounds));
     }
 
     points
 }
 
-// generate pre-clustered points when no input file is supplied.
-// this generates more demo-friendly points than generate_points
-pub fn generate_clustered_points(
+/// `num_points` points scattered around `num_clusters` random centers within
+/// `bounds`, taking turns between the centers. These make for more
+/// demo-friendly input than uniformly random points.
+pub fn generate_clustered_points<R: Rng + ?Sized>(
+    r: &mut R,
     bounds: (&Point, &Point),
     num_clusters: usize,
     num_points: usize,
 ) -> Vec<Point> {
-    let centers = generate_points(bounds, num_clusters);
-
-    let min_bound = {
-        let xbound: f64 = (bounds.1.x - bounds.0.x).abs();
-        let ybound: f64 = (bounds.1.y - bounds.0.y).abs();
+    generate_labelled_points(r, bounds, num_clusters, num_points).0
+}
 
-        match xbound >= ybound {
-            true => ybound,
-            _ => xbound,
-        }
-    };
+/// Points generated as by [`generate_clustered_points`], along with the index
+/// of the center each one was scattered around, which serves as its true label.
+pub fn generate_labelled_points<R: Rng + ?Sized>(
+    r: &mut R,
+    bounds: (&Point, &Point),
+    num_clusters: usize,
+    num_points: usize,
+) -> (Vec<Point>, Vec<usize>) {
+    let centers = generate_points(r, bounds, num_clusters);
+
+    let min_bound = bounds
+        .0
+        .coords
+        .iter()
+        .zip(&bounds.1.coords)
+        .map(|(lower, upper)| (upper - lower).abs())
+        .fold(f64::MAX, f64::min);
     let max_radius = min_bound / (num_clusters as f64);
 
     let mut points = vec![];
+    let mut labels = vec![];
     for ndx in 0..num_points {
         let selection = ndx % centers.len();
         points.push(generate_clustered_point(
+            r,
             bounds,
             centers.get(selection).unwrap(),
             max_radius,
         ));
+        labels.push(selection);
     }
 
-    points
+    (points, labels)
 }
 
-fn generate_clustered_point(
+fn generate_clustered_point<R: Rng + ?Sized>(
+    r: &mut R,
     bounds: (&Point, &Point),
     cluster_center: &Point,
     radius: f64,
 ) -> Point {
-    let mut r = rand::thread_rng();
-    let dist = r.gen_range((-radius)..radius);
-    let angle = r.gen_range(0_f64..(2_f64 * consts::PI));
-
-    let candidate = Point {
-        x: cluster_center.x + (dist * angle.sin()),
-        y: cluster_center.y + (dist * angle.cos()),
-    };
-
-    match candidate {
-        Point { ref x, ref y }
-            if *x >= bounds.0.x && *x < bounds.1.x && *y >= bounds.0.y && *y < bounds.1.y =>
-        {
-            candidate
+    loop {
+        let dist = r.gen_range((-radius)..radius);
+        let direction = random_direction(r, cluster_center.dim());
+
+        let candidate = Point::new(
+            cluster_center
+                .coords
+                .iter()
+                .zip(direction)
+                .map(|(c, d)| c + dist * d)
+                .collect(),
+        );
+
+        if candidate.within(bounds) {
+            return candidate;
         }
-        _ => generate_clustered_point(bounds, cluster_center, radius),
     }
 }
 
-pub fn generate_point(bounds: (&Point, &Point)) -> Point {
-    let mut r = rand::thread_rng();
-    let x: f64 = r.gen_range(bounds.0.x..bounds.1.x);
-    let y: f64 = r.gen_range(bounds.0.y..bounds.1.y);
-
-    Point { x: x, y: y }
-}
-
-#[derive(Debug, Clone, Hash, Serialize)]
-pub struct Centroid {
-    pub p: Point,
-    pub color: usize,
-}
+// unit vector pointing in a uniformly random direction, built by normalizing
+// a vector of standard normal samples (drawn with the Box-Muller transform)
+fn random_direction<R: Rng + ?Sized>(r: &mut R, dim: usize) -> Vec<f64> {
+    let normals: Vec<f64> = (0..dim)
+        .map(|_| {
+            let u1: f64 = 1_f64 - r.gen::<f64>();
+            let u2: f64 = r.gen();
+            (-2_f64 * u1.ln()).sqrt() * (2_f64 * consts::PI * u2).cos()
+        })
+        .collect();
 
-impl PartialEq for Centroid {
-    fn eq(&self, other: &Self) -> bool {
-        return self.p == other.p && self.color == other.color;
+    let norm = normals.iter().map(|n| n * n).sum::<f64>().sqrt();
+    match norm > 0_f64 {
+        true => normals.iter().map(|n| n / norm).collect(),
+        false => random_direction(r, dim),
     }
 }
 
-impl Eq for Centroid {}
+/// Uniformly random point within the inclusive lower and exclusive upper
+/// `bounds`, every coordinate of the lower bound lying below the upper's.
+pub fn generate_point<R: Rng + ?Sized>(r: &mut R, bounds: (&Point, &Point)) -> Point {
+    Point::new(
+        bounds
+            .0
+            .coords
+            .iter()
+            .zip(&bounds.1.coords)
+            .map(|(lower, upper)| r.gen_range(*lower..*upper))
+            .collect(),
+    )
+}
 
+/// Point in as many dimensions as it has coordinates. Every point in a dataset
+/// is expected to share the same dimension.
 #[derive(Debug, Clone, Serialize)]
+#[serde(transparent)]
 pub struct Point {
-    pub x: f64,
-    pub y: f64,
+    /// Coordinate along each dimension.
+    pub coords: Vec<f64>,
 }
 
 const EPSILON: f64 = 0.00001;
 
 impl PartialEq for Point {
     fn eq(&self, other: &Self) -> bool {
-        let diffx = (self.x - other.x).abs();
-        let diffy = (self.y - other.y).abs();
-
-        return diffx < EPSILON && diffy < EPSILON;
+        self.dim() == other.dim()
+            && self
+                .coords
+                .iter()
+                .zip(&other.coords)
+                .all(|(a, b)| (a - b).abs() < EPSILON)
     }
 }
 
-impl Eq for Point {}
-
-impl Hash for Point {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        unsafe {
-            transmute::<f64, u64>(self.x).hash(state);
-            transmute::<f64, u64>(self.y).hash(state);
-        }
-    }
-}
-
-// convert raw String input of the form "11,22.3" into Point
+// convert raw String input of the form "11,22.3" (or "1,2,3,...") into Point
 impl FromStr for Point {
     type Err = ParseFloatError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let coords: Vec<&str> = s.split(',').collect();
-
-        let x_fromstr = coords[0].parse::<f64>()?;
-        let y_fromstr = coords[1].parse::<f64>()?;
+        let coords = s
+            .split(',')
+            .map(|coord| coord.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()?;
 
-        Ok(Point {
-            x: x_fromstr,
-            y: y_fromstr,
-        })
+        Ok(Point::new(coords))
     }
 }
 
 impl Point {
+    /// Point with the given coordinates.
+    pub fn new(coords: Vec<f64>) -> Self {
+        Self { coords }
+    }
+
+    /// Point at the origin of a space with `dim` dimensions.
+    pub fn zero(dim: usize) -> Self {
+        Self::new(vec![0_f64; dim])
+    }
+
+    /// Number of coordinates.
+    pub fn dim(&self) -> usize {
+        self.coords.len()
+    }
+
+    /// Squared Euclidean distance to `other`, which shares its dimension.
     pub fn sum_squared_error(&self, other: &Self) -> f64 {
-        f64::powf(self.x - other.x, 2.0) + f64::powf(self.y - other.y, 2.0)
+        self.coords
+            .iter()
+            .zip(&other.coords)
+            .fold(0_f64, |acc, (a, b)| acc + f64::powf(a - b, 2.0))
+    }
+
+    /// Whether the point lies within the inclusive lower and exclusive upper
+    /// `bounds`.
+    pub fn within(&self, bounds: (&Point, &Point)) -> bool {
+        self.coords
+            .iter()
+            .zip(bounds.0.coords.iter().zip(&bounds.1.coords))
+            .all(|(c, (lower, upper))| c >= lower && c < upper)
     }
 
-    #[allow(dead_code)]
-    pub fn select_initial_centroids(points: &Vec<Point>, k: usize) -> Vec<Centroid> {
-        let mut r = rand::thread_rng();
+    /// Forgy initialization: `k` distinct input points selected at random as
+    /// the initial centroids, or `None` when `points` holds fewer than `k`
+    /// unique points.
+    pub fn select_initial_centroids<R: Rng + ?Sized>(
+        r: &mut R,
+        points: &[Point],
+        k: usize,
+    ) -> Option<Vec<Point>> {
+        // candidates are drawn without replacement (partial Fisher-Yates) and
+        // any candidate equal to an earlier selection is skipped
+        let mut indices: Vec<usize> = (0..points.len()).collect();
+        let mut selections: Vec<Point> = Vec::with_capacity(k);
+        for ndx in 0..indices.len() {
+            if selections.len() == k {
+                break;
+            }
+
+            let swap = r.gen_range(ndx..indices.len());
+            indices.swap(ndx, swap);
 
-        let mut selections = HashMap::new();
-        for color in 1..=k {
-            let candidate = r.gen::<usize>() % points.len();
-            if selections.get(&candidate).is_some() {
+            let candidate = &points[indices[ndx]];
+            if selections.contains(candidate) {
                 continue;
             }
-            let selected = points[candidate].clone();
-            selections.insert(
-                candidate,
-                Centroid {
-                    p: selected,
-                    color: color,
-                },
-            );
+            selections.push(candidate.clone());
         }
 
-        selections.into_values().collect()
+        match selections.len() == k {
+            true => Some(selections),
+            false => None,
+        }
     }
 }
 
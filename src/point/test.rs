@@ -1,69 +1,49 @@
 use super::*;
+use rand::rngs::StdRng;
 
 #[test]
 fn test_gen_clustered_points() {
     let bounds = (
-        &Point { x: 0_f64, y: 0_f64 },
-        &Point {
-            x: 100_f64,
-            y: 100_f64,
-        },
+        &Point::new(vec![0_f64, 0_f64]),
+        &Point::new(vec![100_f64, 100_f64]),
     );
-    let ps = generate_clustered_points(bounds, 3, 9);
+    let ps = generate_clustered_points(&mut rand::thread_rng(), bounds, 3, 9);
 
     assert_eq!(9, ps.len());
     ps.iter().for_each(|p| {
-        assert!(p.x >= bounds.0.x);
-        assert!(p.y >= bounds.0.y);
-        assert!(p.x < bounds.1.x);
-        assert!(p.y < bounds.1.y);
+        assert!(p.within(bounds));
     });
 }
 
 #[test]
 fn test_gen_points() {
     let bounds = (
-        &Point { x: 0_f64, y: 0_f64 },
-        &Point {
-            x: 100_f64,
-            y: 100_f64,
-        },
+        &Point::new(vec![0_f64, 0_f64]),
+        &Point::new(vec![100_f64, 100_f64]),
     );
-    let ps = generate_points(bounds, 13);
+    let ps = generate_points(&mut rand::thread_rng(), bounds, 13);
 
     assert_eq!(13, ps.len());
     ps.iter().for_each(|p| {
-        assert!(p.x >= bounds.0.x);
-        assert!(p.y >= bounds.0.y);
-        assert!(p.x < bounds.1.x);
-        assert!(p.y < bounds.1.y);
+        assert!(p.within(bounds));
     });
 }
 
 #[test]
 fn test_gen_point() {
     let bounds = (
-        &Point { x: 0_f64, y: 0_f64 },
-        &Point {
-            x: 100_f64,
-            y: 100_f64,
-        },
+        &Point::new(vec![0_f64, 0_f64]),
+        &Point::new(vec![100_f64, 100_f64]),
     );
-    let p = generate_point(bounds);
+    let p = generate_point(&mut rand::thread_rng(), bounds);
 
-    assert!(p.x >= bounds.0.x);
-    assert!(p.y >= bounds.0.y);
-    assert!(p.x < bounds.1.x);
-    assert!(p.y < bounds.1.y);
+    assert!(p.within(bounds));
 }
 
 #[test]
 fn test_sum_squared_error() {
-    let p1 = Point {
-        x: -2_f64,
-        y: -4_f64,
-    };
-    let p2 = Point { x: 2_f64, y: 4_f64 };
+    let p1 = Point::new(vec![-2_f64, -4_f64]);
+    let p2 = Point::new(vec![2_f64, 4_f64]);
 
     let result = p1.sum_squared_error(&p2);
     assert_eq!(80_f64, result);
@@ -75,19 +55,80 @@ fn test_point_from_str() {
     assert!(&result.is_ok());
 
     let p: Point = result.unwrap();
-    assert_eq!(22_f64, p.x);
-    assert_eq!(33_f64, p.y);
+    assert_eq!(vec![22_f64, 33_f64], p.coords);
+
+    let p = Point::from_str("1.5,2,-3,4").unwrap();
+    assert_eq!(vec![1.5_f64, 2_f64, -3_f64, 4_f64], p.coords);
+
+    assert!(Point::from_str("1,foo").is_err());
 }
 
 #[test]
 fn test_eq_point() {
-    let p1 = Point { x: 2_f64, y: 4_f64 };
+    let p1 = Point::new(vec![2_f64, 4_f64]);
     let p2 = p1.clone();
-    let p3 = Point {
-        x: 111.1_f64,
-        y: 5555.5_f64,
-    };
+    let p3 = Point::new(vec![111.1_f64, 5555.5_f64]);
 
     assert_eq!(p1, p2);
+    assert_ne!(p1, Point::new(vec![2_f64, 4_f64, 0_f64]));
     assert!(p1 != p3);
 }
+
+#[test]
+fn test_select_initial_centroids() {
+    let points = vec![
+        Point::new(vec![1_f64, 1_f64]),
+        Point::new(vec![1_f64, 1_f64]),
+        Point::new(vec![2_f64, 2_f64]),
+        Point::new(vec![2_f64, 2_f64]),
+        Point::new(vec![3_f64, 3_f64]),
+    ];
+
+    for _ in 0..20 {
+        let centroids =
+            Point::select_initial_centroids(&mut rand::thread_rng(), &points, 3).unwrap();
+        assert_eq!(3, centroids.len());
+
+        for (ndx, c) in centroids.iter().enumerate() {
+            assert!(points.contains(c));
+            assert!(centroids[ndx + 1..].iter().all(|other| other != c));
+        }
+    }
+}
+
+#[test]
+fn test_select_initial_centroids_too_few_unique() {
+    let points = vec![
+        Point::new(vec![1_f64, 1_f64]),
+        Point::new(vec![1_f64, 1_f64]),
+        Point::new(vec![2_f64, 2_f64]),
+    ];
+
+    assert!(Point::select_initial_centroids(&mut rand::thread_rng(), &points, 3).is_none());
+}
+
+#[test]
+fn test_gen_clustered_points_seeded() {
+    let bounds = (
+        &Point::new(vec![0_f64, 0_f64]),
+        &Point::new(vec![100_f64, 100_f64]),
+    );
+
+    let first = generate_clustered_points(&mut StdRng::seed_from_u64(42), bounds, 3, 30);
+    let second = generate_clustered_points(&mut StdRng::seed_from_u64(42), bounds, 3, 30);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_gen_labelled_points() {
+    let bounds = (
+        &Point::new(vec![0_f64, 0_f64]),
+        &Point::new(vec![100_f64, 100_f64]),
+    );
+    let (ps, labels) = generate_labelled_points(&mut StdRng::seed_from_u64(3), bounds, 3, 10);
+    assert_eq!(vec![0, 1, 2, 0, 1, 2, 0, 1, 2, 0], labels);
+
+    // the labels cost no extra randomness
+    let unlabelled = generate_clustered_points(&mut StdRng::seed_from_u64(3), bounds, 3, 10);
+    assert_eq!(unlabelled, ps);
+}
@@ -1,6 +1,12 @@
+use crate::bench::BenchRun;
 use crate::cli::Config;
-use crate::kmeans::Cluster;
-use crate::point::{Centroid, Point};
+use crate::sweep::{SweepResult, SweepRun};
+use kmeans_rs::evaluation::{Agreement, Index, Silhouette};
+use kmeans_rs::kmeans::{KMeansResult, Snapshot};
+use kmeans_rs::{
+    Algorithm, Convergence, DistanceEvaluations, GaussianMixtureModel, History, Init, Medoids,
+    Metric, Point, SplitTree,
+};
 use plotters::prelude::*;
 use rayon::prelude::*;
 use serde::Serialize;
@@ -10,54 +16,513 @@ use std::sync::mpsc::channel;
 
 #[derive(Serialize)]
 struct ClusterJson<'a> {
-    centroid: &'a Centroid,
-    cluster: &'a Vec<&'a Point>,
+    index: usize,
+    centroid: &'a Point,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    medoid: Option<usize>,
+    cluster: Vec<&'a Point>,
+}
+
+#[derive(Serialize)]
+struct MembershipJson<'a> {
+    point: &'a Point,
+    membership: &'a [f64],
 }
 
 #[derive(Serialize)]
 struct IterationJson<'a> {
     iteration: usize,
+    inertia: f64,
     clusters: Vec<ClusterJson<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memberships: Option<Vec<MembershipJson<'a>>>,
+}
+
+#[derive(Serialize)]
+struct RunJson<'a> {
+    seed: Option<u64>,
+    init: Init,
+    metric: Metric,
+    algorithm: Algorithm,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    medoids: Option<Medoids>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fuzzifier: Option<f64>,
+    history: History,
+    convergence: Convergence,
+    inertia: f64,
+    best_restart: usize,
+    restart_inertia: &'a Vec<f64>,
+    distance_evaluations: DistanceEvaluations,
+    iterations: Vec<IterationJson<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    split_tree: Option<&'a SplitTree>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    silhouette: Option<&'a Silhouette>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metrics: Option<MetricsJson>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    agreement: Option<&'a Agreement>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gmm: Option<&'a GaussianMixtureModel>,
+}
+
+#[derive(Serialize)]
+struct BenchJson<'a> {
+    seed: Option<u64>,
+    k: usize,
+    init: Init,
+    metric: Metric,
+    runs: &'a [BenchRun],
+}
+
+#[derive(Serialize)]
+struct SweepJson<'a> {
+    seed: Option<u64>,
+    init: Init,
+    metric: Metric,
+    algorithm: Algorithm,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    criterion: Option<Index>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    references: Option<usize>,
+    elbow: Option<usize>,
+    best: Option<usize>,
+    gap: Option<usize>,
+    runs: &'a [SweepRun],
+}
+
+#[derive(Serialize)]
+struct MetricsJson {
+    silhouette: Option<f64>,
+    davies_bouldin: Option<f64>,
+    calinski_harabasz: Option<f64>,
+    dunn: Option<f64>,
+}
+
+impl MetricsJson {
+    fn new(metrics: &[(Index, Option<f64>)]) -> Self {
+        let value = |index: Index| {
+            metrics
+                .iter()
+                .find(|(i, _)| *i == index)
+                .and_then(|(_, value)| *value)
+        };
+
+        Self {
+            silhouette: value(Index::Silhouette),
+            davies_bouldin: value(Index::DaviesBouldin),
+            calinski_harabasz: value(Index::CalinskiHarabasz),
+            dunn: value(Index::Dunn),
+        }
+    }
 }
 
 // render JSON output for all iterations of K-means performed
-pub fn json_all_iterations(all_clusters: &Vec<Cluster>) -> Result<String, Box<dyn Error>> {
+pub fn json_all_iterations(
+    cfg: &Config,
+    points: &[Point],
+    kmeans_result: &KMeansResult,
+    silhouette: Option<&Silhouette>,
+    metrics: Option<&[(Index, Option<f64>)]>,
+    agreement: Option<&Agreement>,
+    gmm: Option<&GaussianMixtureModel>,
+) -> Result<String, Box<dyn Error>> {
     let mut result = vec![];
-    for (iteration, clusters) in all_clusters.iter().enumerate() {
+    for snapshot in &kmeans_result.history {
         let formatted = IterationJson {
-            iteration: iteration,
-            clusters: clusters
+            iteration: snapshot.iteration,
+            inertia: snapshot.inertia,
+            clusters: snapshot
+                .centroids
                 .iter()
-                .map(|(k, v)| ClusterJson {
-                    centroid: k,
-                    cluster: v,
+                .zip(snapshot.members(points))
+                .enumerate()
+                .map(|(index, (centroid, cluster))| ClusterJson {
+                    index,
+                    centroid,
+                    medoid: snapshot.medoids.as_ref().map(|medoids| medoids[index]),
+                    cluster,
                 })
                 .collect(),
+            memberships: snapshot.memberships.as_ref().map(|memberships| {
+                points
+                    .iter()
+                    .zip(memberships)
+                    .map(|(point, membership)| MembershipJson { point, membership })
+                    .collect()
+            }),
         };
 
         result.push(formatted);
     }
 
-    let rendered = serde_json::to_string(&result)?;
+    let run = RunJson {
+        seed: cfg.seed,
+        init: cfg.init,
+        metric: kmeans_result.metric,
+        algorithm: cfg.algorithm,
+        medoids: cfg.medoids,
+        fuzzifier: cfg.fuzzy,
+        history: cfg.history,
+        convergence: kmeans_result.convergence,
+        inertia: kmeans_result.inertia,
+        best_restart: kmeans_result.best_restart,
+        restart_inertia: &kmeans_result.restart_inertia,
+        distance_evaluations: kmeans_result.distance_evaluations,
+        iterations: result,
+        split_tree: kmeans_result.clustering.split_tree.as_ref(),
+        silhouette,
+        metrics: metrics.map(MetricsJson::new),
+        agreement,
+        gmm,
+    };
+
+    let rendered = serde_json::to_string(&run)?;
+    Ok(rendered)
+}
+
+// render JSON output for every benchmarked algorithm
+pub fn json_bench(cfg: &Config, runs: &[BenchRun]) -> Result<String, Box<dyn Error>> {
+    let bench = BenchJson {
+        seed: cfg.seed,
+        k: cfg.k,
+        init: cfg.init,
+        metric: cfg.metric,
+        runs,
+    };
+
+    let rendered = serde_json::to_string(&bench)?;
+    Ok(rendered)
+}
+
+// render a plain text table comparing every benchmarked algorithm
+pub fn table_bench(runs: &[BenchRun]) -> String {
+    let mut table = format!(
+        "{:<10} {:>10} {:>10} {:>16} {:>14} {:>14} {:>8} {:>8}\n",
+        "algorithm",
+        "seconds",
+        "iterations",
+        "inertia",
+        "computed",
+        "skipped",
+        "speedup",
+        "matches"
+    );
+    for run in runs {
+        table.push_str(&format!(
+            "{:<10} {:>10.3} {:>10} {:>16.2} {:>14} {:>14} {:>7.2}x {:>8}\n",
+            run.algorithm.to_string(),
+            run.seconds,
+            run.iterations,
+            run.inertia,
+            run.distance_evaluations.computed,
+            run.distance_evaluations.skipped,
+            run.speedup,
+            run.matches_baseline
+        ));
+    }
+
+    table
+}
+
+// render JSON output for every k swept, along with the elbow and any k the
+// criterion or gap statistic chose
+pub fn json_sweep(cfg: &Config, sweep: &SweepResult) -> Result<String, Box<dyn Error>> {
+    let sweep = SweepJson {
+        seed: cfg.seed,
+        init: cfg.init,
+        metric: cfg.metric,
+        algorithm: cfg.algorithm,
+        criterion: sweep.criterion,
+        references: sweep.references,
+        elbow: sweep.elbow,
+        best: sweep.best,
+        gap: sweep.gap,
+        runs: &sweep.runs,
+    };
+
+    let rendered = serde_json::to_string(&sweep)?;
     Ok(rendered)
 }
 
-// render PNG for all iterations of K-means
+// render a plain text table of every k swept, marking the elbow, the best k
+// under the criterion and the k chosen by the gap statistic
+pub fn table_sweep(sweep: &SweepResult) -> String {
+    let mut table = format!(
+        "{:>4} {:>16} {:>10} {:>9}",
+        "k", "inertia", "iterations", "converged"
+    );
+    if let Some(index) = sweep.criterion {
+        table.push_str(&format!(" {:>18}", index.to_string()));
+    }
+    if sweep.references.is_some() {
+        table.push_str(&format!(" {:>8} {:>8}", "gap", "s.e."));
+    }
+    table.push('\n');
+
+    for run in &sweep.runs {
+        table.push_str(&format!(
+            "{:>4} {:>16.2} {:>10} {:>9}",
+            run.k, run.inertia, run.iterations, run.converged
+        ));
+        if sweep.criterion.is_some() {
+            match run.score {
+                Some(score) => table.push_str(&format!(" {:>18.4}", score)),
+                None => table.push_str(&format!(" {:>18}", "-")),
+            }
+        }
+        if sweep.references.is_some() {
+            match (run.gap, run.gap_error) {
+                (Some(gap), Some(error)) => {
+                    table.push_str(&format!(" {:>8.4} {:>8.4}", gap, error))
+                }
+                _ => table.push_str(&format!(" {:>8} {:>8}", "-", "-")),
+            }
+        }
+        if sweep.elbow == Some(run.k) {
+            table.push_str("  <- elbow");
+        }
+        if sweep.criterion.is_some() && sweep.best == Some(run.k) {
+            table.push_str("  <- best");
+        }
+        if sweep.gap == Some(run.k) {
+            table.push_str("  <- gap");
+        }
+        table.push('\n');
+    }
+
+    table
+}
+
+// render a plain text summary of the final clustering's validity indices
+pub fn table_metrics(result: &KMeansResult, metrics: &[(Index, Option<f64>)]) -> String {
+    let mut table = format!("{:<18} {:>16}\n", "k", result.clustering.centroids.len());
+    table.push_str(&format!("{:<18} {:>16.2}\n", "inertia", result.inertia));
+    for (index, value) in metrics {
+        let value = match value {
+            Some(value) => format!("{:.4}", value),
+            None => "-".to_string(),
+        };
+        table.push_str(&format!("{:<18} {:>16}\n", index.to_string(), value));
+    }
+
+    table
+}
+
+// render a plain text summary of how well the final clustering agrees with the
+// true labels, followed by their contingency table
+pub fn table_agreement(agreement: &Agreement) -> String {
+    let mut table = String::new();
+    for (name, value) in [
+        ("adjusted rand index", agreement.adjusted_rand_index),
+        (
+            "normalized mutual information",
+            agreement.normalized_mutual_information,
+        ),
+        ("homogeneity", agreement.homogeneity),
+        ("completeness", agreement.completeness),
+        ("v-measure", agreement.v_measure),
+        ("purity", agreement.purity),
+    ] {
+        table.push_str(&format!("{:<30} {:>10.4}\n", name, value));
+    }
+
+    let contingency = &agreement.contingency;
+    table.push_str("\ntrue label \\ cluster\n");
+    table.push_str(&format!("{:>10}", ""));
+    for cluster in &contingency.clusters {
+        table.push_str(&format!(" {:>6}", cluster));
+    }
+    table.push('\n');
+    for (class, row) in contingency.classes.iter().zip(&contingency.counts) {
+        table.push_str(&format!("{:>10}", class));
+        for count in row {
+            table.push_str(&format!(" {:>6}", count));
+        }
+        table.push('\n');
+    }
+
+    table
+}
+
+// render a plain text summary of the fitted Gaussian mixture
+pub fn table_gmm(gmm: &GaussianMixtureModel) -> String {
+    let mut table = format!("{:<18} {:>16}\n", "components", gmm.means.len());
+    table.push_str(&format!(
+        "{:<18} {:>16}\n",
+        "covariance",
+        gmm.covariance.to_string()
+    ));
+    table.push_str(&format!("{:<18} {:>16}\n", "converged", gmm.converged));
+    table.push_str(&format!("{:<18} {:>16}\n", "iterations", gmm.iterations));
+    for (name, value) in [
+        ("log-likelihood", gmm.log_likelihood),
+        ("aic", gmm.aic),
+        ("bic", gmm.bic),
+    ] {
+        table.push_str(&format!("{:<18} {:>16.2}\n", name, value));
+    }
+
+    table
+}
+
+// render PNG of the inertia reached by every k swept with the elbow marked,
+// of the criterion's score with the best k marked, if there is one, and of the
+// gap statistic with the k it chose marked, if it was computed
+pub fn png_sweep(cfg: &Config, sweep: &SweepResult) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let ks = (sweep.runs[0].k, sweep.runs[sweep.runs.len() - 1].k);
+    let inertia: Vec<(usize, f64, Option<f64>)> = sweep
+        .runs
+        .iter()
+        .map(|run| (run.k, run.inertia, None))
+        .collect();
+    png_curve(
+        cfg,
+        "elbow.png",
+        "Elbow",
+        ks,
+        "inertia",
+        &inertia,
+        sweep.elbow.map(|k| (k, "elbow")),
+    )?;
+
+    if let Some(index) = sweep.criterion {
+        let scores: Vec<(usize, f64, Option<f64>)> = sweep
+            .runs
+            .iter()
+            .filter_map(|run| run.score.map(|score| (run.k, score, None)))
+            .collect();
+        let title = match index.higher_is_better() {
+            true => format!("{} (higher is better)", index),
+            false => format!("{} (lower is better)", index),
+        };
+        png_curve(
+            cfg,
+            &format!("{}.png", index),
+            &title,
+            ks,
+            &index.to_string(),
+            &scores,
+            sweep.best.map(|k| (k, "best")),
+        )?;
+    }
+
+    if let Some(references) = sweep.references {
+        let gaps: Vec<(usize, f64, Option<f64>)> = sweep
+            .runs
+            .iter()
+            .filter_map(|run| run.gap.map(|gap| (run.k, gap, run.gap_error)))
+            .collect();
+        png_curve(
+            cfg,
+            "gap.png",
+            &format!("Gap, B={}", references),
+            ks,
+            "gap",
+            &gaps,
+            sweep.gap.map(|k| (k, "gap")),
+        )?;
+    }
+
+    Ok(())
+}
+
+// render PNG of a curve over a range of k, each value with an optional error
+// bar of one standard error either side, and with one k circled and labelled
+fn png_curve(
+    cfg: &Config,
+    filename: &str,
+    title: &str,
+    ks: (usize, usize),
+    y_desc: &str,
+    curve: &[(usize, f64, Option<f64>)],
+    marked: Option<(usize, &str)>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let filename = cfg.png_out.join(PathBuf::from(filename));
+    let error = |e: &Option<f64>| e.unwrap_or(0_f64);
+    let lowest = curve
+        .iter()
+        .fold(0_f64, |acc, (_, v, e)| f64::min(acc, v - error(e)));
+    let highest = curve
+        .iter()
+        .fold(0_f64, |acc, (_, v, e)| f64::max(acc, v + error(e)));
+    let margin = match highest > lowest {
+        true => (highest - lowest) * 0.05,
+        false => 1_f64,
+    };
+
+    let root = BitMapBackend::new(&filename, (1024, 768)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(
+            format!("{} (k={}..{})", title, ks.0, ks.1),
+            ("sans-serif", 50).into_font(),
+        )
+        .margin(5_u32)
+        .x_label_area_size(40_u32)
+        .y_label_area_size(80_u32)
+        .build_cartesian_2d(ks.0..ks.1, (lowest - margin)..(highest + margin))?;
+    chart
+        .configure_mesh()
+        .x_desc("k")
+        .y_desc(y_desc)
+        .x_labels(ks.1 - ks.0 + 1)
+        .y_label_formatter(&|v| format!("{:.2e}", v))
+        .draw()?;
+
+    chart.draw_series(LineSeries::new(
+        curve.iter().map(|(k, v, _)| (*k, *v)),
+        BLUE.stroke_width(2),
+    ))?;
+    chart.draw_series(
+        curve
+            .iter()
+            .map(|(k, v, _)| Circle::new((*k, *v), 5, BLUE.filled())),
+    )?;
+    chart.draw_series(curve.iter().filter_map(|(k, v, e)| {
+        e.map(|e| ErrorBar::new_vertical(*k, v - e, *v, v + e, BLUE.stroke_width(2), 10))
+    }))?;
+
+    if let Some((k, v, _)) = marked.and_then(|(k, _)| curve.iter().find(|(x, _, _)| *x == k)) {
+        let label = marked.unwrap().1;
+        chart.draw_series(PointSeries::of_element(
+            [(*k, *v)],
+            10,
+            &RED,
+            &|c, s: u32, st| {
+                EmptyElement::at(c)
+                    + Circle::new((0, 0), s, st.stroke_width(3))
+                    + Text::new(
+                        format!("{} (k={})", label, c.0),
+                        (15, -15),
+                        ("sans-serif", 20).into_font(),
+                    )
+            },
+        ))?;
+    }
+
+    Ok(())
+}
+
+// render PNG for all iterations of K-means, which requires 2D points
 pub fn png_all_iterations(
     cfg: &Config,
-    all_clusters: &Vec<Cluster>,
+    points: &[Point],
+    history: &[Snapshot],
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
     let (sender, receiver) = channel();
 
-    (0_usize..all_clusters.len())
+    history
         .into_par_iter()
-        .for_each_with(sender, |s, iter| {
-            if let Err(e) = png_for_iteration(cfg, all_clusters.get(iter).unwrap(), iter) {
+        .for_each_with(sender, |s, snapshot| {
+            if let Err(e) = png_for_iteration(cfg, points, snapshot) {
                 s.send(e).unwrap()
             }
         });
 
-    for render_err in receiver {
+    if let Ok(render_err) = receiver.try_recv() {
         return Err(render_err);
     }
 
@@ -67,9 +532,10 @@ pub fn png_all_iterations(
 // render PNG for a single K-means iteration
 fn png_for_iteration(
     cfg: &Config,
-    clusters: &Cluster,
-    iter: usize,
+    points: &[Point],
+    snapshot: &Snapshot,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let iter = snapshot.iteration;
     let bounds = cfg.bounds();
     let filename = cfg
         .png_out
@@ -83,35 +549,232 @@ fn png_for_iteration(
             format!("K-means (k={}, iteration={})", cfg.k, iter),
             ("sans-serif", 50).into_font(),
         )
-        .margin(5 as u32)
-        .x_label_area_size(30 as u32)
-        .y_label_area_size(30 as u32)
-        .build_cartesian_2d((bounds.0.x)..(bounds.1.x), (bounds.0.y)..(bounds.1.y))?;
+        .margin(5_u32)
+        .x_label_area_size(30_u32)
+        .y_label_area_size(30_u32)
+        .build_cartesian_2d(
+            (bounds.0.coords[0])..(bounds.1.coords[0]),
+            (bounds.0.coords[1])..(bounds.1.coords[1]),
+        )?;
     chart.configure_mesh().draw()?;
 
-    for (centroid, points) in clusters {
+    match &snapshot.memberships {
+        // fuzzy runs shade each point by how strongly it belongs to its
+        // cluster, fading out where memberships are split evenly
+        Some(memberships) => {
+            let labels = snapshot.labels(points);
+            chart.draw_series(points.iter().zip(labels).zip(memberships).map(
+                |((p, label), membership)| {
+                    let color = Palette99::pick(label + 1).mix(membership_strength(membership));
+                    Circle::new((p.coords[0], p.coords[1]), 5, color.filled())
+                },
+            ))?;
+        }
+        None => {
+            for (ndx, cluster) in snapshot.members(points).iter().enumerate() {
+                chart.draw_series(PointSeries::of_element(
+                    cluster.iter().map(|p| (p.coords[0], p.coords[1])),
+                    5,
+                    &Palette99::pick(ndx + 1),
+                    &|c, s: u32, st| EmptyElement::at(c) + Circle::new((0, 0), s, st.filled()),
+                ))?;
+            }
+        }
+    }
+
+    for centroid in &snapshot.centroids {
         chart.draw_series(PointSeries::of_element(
-            points.iter().map(|p| (p.x, p.y)),
+            [(centroid.coords[0], centroid.coords[1])],
             5,
-            &Palette99::pick(centroid.color),
-            &|c, s: u32, st| return EmptyElement::at(c) + Circle::new((0, 0), s, st.filled()),
+            &BLACK,
+            &|c, s: u32, st| {
+                EmptyElement::at(c)
+                    + Circle::new((0, 0), s, st.filled())
+                    + Text::new(
+                        format!("({:.1}, {:.1})", c.0, c.1),
+                        (10, 0),
+                        ("sans-serif", 12).into_font(),
+                    )
+            },
         ))?;
+    }
+
+    Ok(())
+}
+
+// render PNG for a fitted Gaussian mixture, with every point colored by its
+// most probable component and each component's 1 and 2 standard deviation
+// ellipses drawn around its mean
+pub fn png_gmm(
+    cfg: &Config,
+    points: &[Point],
+    model: &GaussianMixtureModel,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let bounds = cfg.bounds();
+    let filename = cfg.png_out.join(PathBuf::from("gmm.png"));
+
+    let root = BitMapBackend::new(&filename, (1024, 1024)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(
+            format!("GMM (k={}, covariance={})", cfg.k, model.covariance),
+            ("sans-serif", 50).into_font(),
+        )
+        .margin(5_u32)
+        .x_label_area_size(30_u32)
+        .y_label_area_size(30_u32)
+        .build_cartesian_2d(
+            (bounds.0.coords[0])..(bounds.1.coords[0]),
+            (bounds.0.coords[1])..(bounds.1.coords[1]),
+        )?;
+    chart.configure_mesh().draw()?;
+
+    chart.draw_series(
+        points
+            .iter()
+            .zip(&model.labels)
+            .zip(&model.responsibilities)
+            .map(|((p, label), responsibilities)| {
+                let color = Palette99::pick(label + 1).mix(membership_strength(responsibilities));
+                Circle::new((p.coords[0], p.coords[1]), 5, color.filled())
+            }),
+    )?;
+
+    for (ndx, (mean, covariance)) in model.means.iter().zip(&model.covariances).enumerate() {
+        for deviations in [1_f64, 2_f64] {
+            chart.draw_series(std::iter::once(PathElement::new(
+                ellipse(mean, covariance, deviations),
+                Palette99::pick(ndx + 1).stroke_width(2),
+            )))?;
+        }
 
         chart.draw_series(PointSeries::of_element(
-            [(centroid.p.x, centroid.p.y)],
+            [(mean.coords[0], mean.coords[1])],
             5,
             &BLACK,
             &|c, s: u32, st| {
-                return EmptyElement::at(c)
+                EmptyElement::at(c)
                     + Circle::new((0, 0), s, st.filled())
                     + Text::new(
                         format!("({:.1}, {:.1})", c.0, c.1),
                         (10, 0),
                         ("sans-serif", 12).into_font(),
-                    );
+                    )
             },
         ))?;
     }
 
     Ok(())
 }
+
+// closed outline of the points the given number of standard deviations from
+// mean under a 2D covariance, along the axes of its eigendecomposition
+fn ellipse(mean: &Point, covariance: &[Vec<f64>], deviations: f64) -> Vec<(f64, f64)> {
+    let (a, b, c) = (covariance[0][0], covariance[0][1], covariance[1][1]);
+    let spread = (((a - c) / 2_f64).powi(2) + b * b).sqrt();
+    let major = f64::max(0_f64, (a + c) / 2_f64 + spread).sqrt() * deviations;
+    let minor = f64::max(0_f64, (a + c) / 2_f64 - spread).sqrt() * deviations;
+    let angle = 0.5 * f64::atan2(2_f64 * b, a - c);
+
+    (0..=100)
+        .map(|step| {
+            let t = 2_f64 * std::f64::consts::PI * step as f64 / 100_f64;
+            let (x, y) = (major * t.cos(), minor * t.sin());
+            (
+                mean.coords[0] + x * angle.cos() - y * angle.sin(),
+                mean.coords[1] + x * angle.sin() + y * angle.cos(),
+            )
+        })
+        .collect()
+}
+
+// render a silhouette plot: one bar per scored point, grouped by cluster and
+// sorted within each, with the overall score marked by a vertical line
+pub fn png_silhouette(
+    cfg: &Config,
+    silhouette: &Silhouette,
+    labels: &[usize],
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let filename = cfg.png_out.join(PathBuf::from("silhouette.png"));
+
+    let mut clusters: Vec<Vec<f64>> = vec![vec![]; silhouette.clusters.len()];
+    for (value, label) in silhouette.points.iter().zip(labels) {
+        if let Some(value) = value {
+            clusters[*label].push(*value);
+        }
+    }
+    clusters
+        .iter_mut()
+        .for_each(|values| values.sort_by(|a, b| b.total_cmp(a)));
+
+    // clusters are stacked bottom up, separated by a small gap
+    let scored: usize = clusters.iter().map(Vec::len).sum();
+    let gap = usize::max(1, scored / 50);
+    let height = scored + gap * (clusters.len() + 1);
+    let lowest = silhouette
+        .points
+        .iter()
+        .flatten()
+        .fold(-0.1, |acc: f64, v| f64::min(acc, *v));
+
+    let root = BitMapBackend::new(&filename, (1024, 1024)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(
+            format!(
+                "Silhouettes (k={}, score={:.3}{})",
+                clusters.len(),
+                silhouette.score,
+                match silhouette.sampled {
+                    true => ", sampled",
+                    false => "",
+                }
+            ),
+            ("sans-serif", 40).into_font(),
+        )
+        .margin(5_u32)
+        .x_label_area_size(40_u32)
+        .y_label_area_size(40_u32)
+        .build_cartesian_2d(lowest..1_f64, 0_f64..height as f64)?;
+    chart
+        .configure_mesh()
+        .disable_y_mesh()
+        .disable_y_axis()
+        .x_desc("silhouette")
+        .draw()?;
+
+    let mut bottom = gap;
+    for (ndx, values) in clusters.iter().enumerate() {
+        let color = Palette99::pick(ndx + 1);
+        chart.draw_series(values.iter().enumerate().map(|(row, value)| {
+            let y = (bottom + values.len() - row) as f64;
+            Rectangle::new([(0_f64, y - 1_f64), (*value, y)], color.filled())
+        }))?;
+        chart.draw_series(std::iter::once(Text::new(
+            format!("{}", ndx),
+            (lowest, (bottom + values.len() / 2) as f64),
+            ("sans-serif", 16).into_font(),
+        )))?;
+        bottom += values.len() + gap;
+    }
+
+    chart.draw_series(LineSeries::new(
+        [(silhouette.score, 0_f64), (silhouette.score, height as f64)],
+        RED.stroke_width(2),
+    ))?;
+
+    Ok(())
+}
+
+// opacity for a point with the given memberships: its largest membership,
+// rescaled so an even split across every cluster is faint and certainty opaque
+fn membership_strength(membership: &[f64]) -> f64 {
+    let even = 1_f64 / membership.len() as f64;
+    let largest = membership.iter().fold(0_f64, |acc, u| f64::max(acc, *u));
+    match membership.len() {
+        1 => 1_f64,
+        _ => 0.1 + 0.9 * ((largest - even) / (1_f64 - even)).clamp(0_f64, 1_f64),
+    }
+}
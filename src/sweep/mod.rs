@@ -0,0 +1,239 @@
+use crate::cli::{Config, Sweep};
+use kmeans_rs::evaluation::Index;
+use kmeans_rs::kmeans::{self, KMeansError};
+use kmeans_rs::point::generate_point;
+use kmeans_rs::{History, Point};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+
+// outcome of clustering the input points with a single k
+#[derive(Debug, Serialize)]
+pub struct SweepRun {
+    pub k: usize,
+    pub inertia: f64,
+    pub iterations: usize,
+    pub converged: bool,
+    // value of the sweep's criterion, when one was given and is defined for
+    // this clustering
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    // gap statistic, when reference datasets were asked for and every
+    // dispersion involved is positive
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gap: Option<f64>,
+    // standard error of the gap statistic, simulation error included
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gap_error: Option<f64>,
+}
+
+// every k tried, in increasing order, along with the elbow of their inertia and
+// the k chosen by the criterion
+#[derive(Debug, Serialize)]
+pub struct SweepResult {
+    // index the best k was chosen by, or the elbow when none
+    pub criterion: Option<Index>,
+    pub runs: Vec<SweepRun>,
+    // the k past which adding clusters stops paying off, if the curve bends
+    pub elbow: Option<usize>,
+    // the k scoring best under the criterion, or the elbow without one
+    pub best: Option<usize>,
+    // number of reference datasets the gap statistic was computed against
+    pub references: Option<usize>,
+    // the k chosen by the gap statistic under the 1-SE rule
+    pub gap: Option<usize>,
+}
+
+// cluster points with every k in the sweep's range, each from its own seed
+// drawn up front, and locate the elbow of the resulting inertia curve. with a
+// criterion, each clustering is also scored and the best one picked, and with
+// reference datasets the gap statistic picks a k of its own
+pub fn run<R: Rng + ?Sized>(
+    cfg: &Config,
+    sweep: &Sweep,
+    points: &[Point],
+    r: &mut R,
+) -> Result<SweepResult, Box<KMeansError>> {
+    let seeds: Vec<u64> = (sweep.min_k..=sweep.max_k).map(|_| r.gen()).collect();
+
+    let mut runs = vec![];
+    for (k, seed) in (sweep.min_k..=sweep.max_k).zip(seeds) {
+        eprintln!("kmeans-rs: sweeping k={}", k);
+        let params = cfg.kmeans_for(k).history(History::Final);
+        let r = &mut StdRng::seed_from_u64(seed);
+        let result = kmeans::execute(&params, points, r)?;
+        let score = sweep.criterion.and_then(|index| {
+            index.score(
+                &result.metric,
+                points,
+                &result.clustering,
+                cfg.silhouette_sample,
+                r,
+            )
+        });
+        runs.push(SweepRun {
+            k,
+            inertia: result.inertia,
+            iterations: result.convergence.iterations,
+            converged: result.convergence.converged,
+            score,
+            gap: None,
+            gap_error: None,
+        });
+    }
+
+    // seeds for the reference datasets come after those of the input's runs,
+    // so asking for the gap statistic leaves the runs themselves unchanged
+    let reference_seeds: Vec<u64> = (0..sweep.gap.unwrap_or(0)).map(|_| r.gen()).collect();
+    let references = reference_dispersions(cfg, sweep, points.len(), &reference_seeds)?;
+    for (ndx, run) in runs.iter_mut().enumerate() {
+        let dispersions: Vec<f64> = references.iter().map(|ref_run| ref_run[ndx]).collect();
+        if let Some((gap, error)) = gap_statistic(run.inertia, &dispersions) {
+            run.gap = Some(gap);
+            run.gap_error = Some(error);
+        }
+    }
+    let gap = one_standard_error(&runs);
+    if let Some(k) = gap {
+        eprintln!("kmeans-rs: the gap statistic chose k={}", k);
+    }
+
+    let ks: Vec<usize> = runs.iter().map(|run| run.k).collect();
+    let inertias: Vec<f64> = runs.iter().map(|run| run.inertia).collect();
+    let elbow = elbow(&ks, &inertias);
+    if let Some(k) = elbow {
+        eprintln!("kmeans-rs: found the elbow at k={}", k);
+    }
+
+    let best = match sweep.criterion {
+        Some(index) => best(index, &runs),
+        None => elbow,
+    };
+    if let (Some(index), Some(k)) = (sweep.criterion, best) {
+        eprintln!("kmeans-rs: {} is best at k={}", index, k);
+    }
+
+    Ok(SweepResult {
+        criterion: sweep.criterion,
+        runs,
+        elbow,
+        best,
+        references: sweep.gap,
+        gap,
+    })
+}
+
+// inertia of every k in the sweep's range for each reference dataset: as many
+// points as the input, drawn uniformly within the configured bounds. each
+// reference is generated and clustered from its own seed
+fn reference_dispersions(
+    cfg: &Config,
+    sweep: &Sweep,
+    num_points: usize,
+    seeds: &[u64],
+) -> Result<Vec<Vec<f64>>, Box<KMeansError>> {
+    let mut dispersions = vec![];
+    for (reference, seed) in seeds.iter().enumerate() {
+        eprintln!(
+            "kmeans-rs: sweeping reference dataset {} of {}",
+            reference + 1,
+            seeds.len()
+        );
+        let r = &mut StdRng::seed_from_u64(*seed);
+        let points: Vec<Point> = (0..num_points)
+            .map(|_| generate_point(r, cfg.bounds()))
+            .collect();
+
+        let mut inertias = vec![];
+        for k in sweep.min_k..=sweep.max_k {
+            let params = cfg.kmeans_for(k).history(History::Final);
+            inertias.push(kmeans::execute(&params, &points, r)?.inertia);
+        }
+        dispersions.push(inertias);
+    }
+
+    Ok(dispersions)
+}
+
+// gap statistic (Tibshirani, Walther and Hastie, 2001) of one k: how far the
+// log dispersion of the input falls below the mean log dispersion of the
+// reference datasets, along with the standard deviation of the latter scaled
+// by sqrt(1 + 1/B) to account for simulating B references. under squared
+// Euclidean distance the inertia is exactly their pooled within-cluster sum of
+// squares. none without references, or when a dispersion is not positive
+fn gap_statistic(dispersion: f64, references: &[f64]) -> Option<(f64, f64)> {
+    if references.is_empty() || dispersion <= 0_f64 || references.iter().any(|w| *w <= 0_f64) {
+        return None;
+    }
+
+    let b = references.len() as f64;
+    let logs: Vec<f64> = references.iter().map(|w| w.ln()).collect();
+    let mean = logs.iter().sum::<f64>() / b;
+    let deviation = (logs.iter().map(|l| (l - mean).powi(2)).sum::<f64>() / b).sqrt();
+
+    Some((
+        mean - dispersion.ln(),
+        deviation * (1_f64 + 1_f64 / b).sqrt(),
+    ))
+}
+
+// the 1-SE rule: the smallest k whose gap is no more than one standard error
+// below the gap of the next k. the largest k swept has no next k to compare
+// with, so it is never chosen
+fn one_standard_error(runs: &[SweepRun]) -> Option<usize> {
+    runs.windows(2)
+        .find(|pair| match (pair[0].gap, pair[1].gap, pair[1].gap_error) {
+            (Some(gap), Some(next), Some(error)) => gap >= next - error,
+            _ => false,
+        })
+        .map(|pair| pair[0].k)
+}
+
+// the k with the best score under index, breaking ties toward the smallest k
+fn best(index: Index, runs: &[SweepRun]) -> Option<usize> {
+    runs.iter()
+        .filter_map(|run| run.score.map(|score| (run.k, score)))
+        .fold(None, |acc: Option<(usize, f64)>, (k, score)| match acc {
+            Some((_, best)) if score == best => acc,
+            Some((_, best)) if (score > best) != index.higher_is_better() => acc,
+            _ => Some((k, score)),
+        })
+        .map(|(k, _)| k)
+}
+
+// kneedle (Satopää et al., 2011) on a decreasing, convex curve: once both axes
+// are scaled to [0, 1], the elbow is the point lying farthest below the
+// straight line joining the first point to the last. a curve that never dips
+// below that line has no elbow, and neither does one with fewer than 3 points
+fn elbow(ks: &[usize], values: &[f64]) -> Option<usize> {
+    if ks.len() < 3 {
+        return None;
+    }
+
+    let (first_k, last_k) = (ks[0] as f64, ks[ks.len() - 1] as f64);
+    let highest = values.iter().fold(f64::MIN, |acc, v| f64::max(acc, *v));
+    let lowest = values.iter().fold(f64::MAX, |acc, v| f64::min(acc, *v));
+    if highest <= lowest {
+        return None;
+    }
+
+    // the largest difference between the line and the curve, breaking ties
+    // toward the smallest k
+    let (elbow, difference) =
+        ks.iter()
+            .zip(values)
+            .fold((None, 0_f64), |acc: (Option<usize>, f64), (k, value)| {
+                let x = (*k as f64 - first_k) / (last_k - first_k);
+                let y = (value - lowest) / (highest - lowest);
+                let difference = (1_f64 - x) - y;
+                match difference > acc.1 {
+                    true => (Some(*k), difference),
+                    false => acc,
+                }
+            });
+
+    elbow.filter(|_| difference > 0_f64)
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,110 @@
+use super::*;
+
+#[test]
+fn test_elbow() {
+    let ks = vec![1, 2, 3, 4, 5, 6];
+    let inertias = vec![1000_f64, 400_f64, 120_f64, 100_f64, 90_f64, 85_f64];
+    assert_eq!(Some(3), elbow(&ks, &inertias));
+
+    // the elbow does not depend on the scale of either axis
+    let ks: Vec<usize> = ks.iter().map(|k| k + 10).collect();
+    let scaled: Vec<f64> = inertias.iter().map(|i| i * 1e6).collect();
+    assert_eq!(Some(13), elbow(&ks, &scaled));
+}
+
+#[test]
+fn test_elbow_on_ties() {
+    // both 2 and 3 lie equally far below the line, so the smaller k wins
+    let ks = vec![1, 2, 3, 4, 5];
+    let inertias = vec![8_f64, 3_f64, 1_f64, 0_f64, 0_f64];
+    assert_eq!(Some(2), elbow(&ks, &inertias));
+}
+
+#[test]
+fn test_no_elbow() {
+    // a straight line, a concave curve, a flat curve and too few points
+    let line = [5_f64, 4_f64, 3_f64, 2_f64, 1_f64];
+    assert_eq!(None, elbow(&[1, 2, 3, 4, 5], &line));
+    assert_eq!(None, elbow(&[1, 2, 3, 4], &[16_f64, 15_f64, 12_f64, 7_f64]));
+    assert_eq!(None, elbow(&[1, 2, 3], &[5_f64, 5_f64, 5_f64]));
+    assert_eq!(None, elbow(&[1, 2], &[10_f64, 1_f64]));
+}
+
+fn scored(scores: &[Option<f64>]) -> Vec<SweepRun> {
+    scores
+        .iter()
+        .enumerate()
+        .map(|(ndx, score)| SweepRun {
+            k: ndx + 1,
+            inertia: 0_f64,
+            iterations: 1,
+            converged: true,
+            score: *score,
+            gap: None,
+            gap_error: None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_best() {
+    let runs = scored(&[None, Some(0.4), Some(0.7), Some(0.7), Some(0.2)]);
+    assert_eq!(Some(3), best(Index::Silhouette, &runs));
+    assert_eq!(Some(5), best(Index::DaviesBouldin, &runs));
+
+    assert_eq!(None, best(Index::Dunn, &scored(&[None, None])));
+}
+
+#[test]
+fn test_gap_statistic() {
+    // references at e^2 and e^4 against an input at e
+    let references = [2_f64.exp(), 4_f64.exp()];
+    let (gap, error) = gap_statistic(1_f64.exp(), &references).unwrap();
+    assert!((gap - 2_f64).abs() < 1e-12);
+    assert!((error - 1.5_f64.sqrt()).abs() < 1e-12);
+
+    // identical references leave no error
+    let (gap, error) = gap_statistic(10_f64, &[10_f64; 4]).unwrap();
+    assert!(gap.abs() < 1e-12);
+    assert_eq!(0_f64, error);
+
+    assert_eq!(None, gap_statistic(10_f64, &[]));
+    assert_eq!(None, gap_statistic(0_f64, &[10_f64]));
+    assert_eq!(None, gap_statistic(10_f64, &[10_f64, 0_f64]));
+}
+
+fn gapped(gaps: &[(f64, f64)]) -> Vec<SweepRun> {
+    gaps.iter()
+        .enumerate()
+        .map(|(ndx, (gap, error))| SweepRun {
+            k: ndx + 1,
+            inertia: 0_f64,
+            iterations: 1,
+            converged: true,
+            score: None,
+            gap: Some(*gap),
+            gap_error: Some(*error),
+        })
+        .collect()
+}
+
+#[test]
+fn test_one_standard_error() {
+    // the gap keeps rising until k=3, after which the next rise is within
+    // one standard error
+    let runs = gapped(&[
+        (0.1, 0.05),
+        (0.5, 0.05),
+        (0.9, 0.05),
+        (0.95, 0.1),
+        (0.7, 0.1),
+    ]);
+    assert_eq!(Some(3), one_standard_error(&runs));
+
+    // a gap that keeps rising never settles
+    let runs = gapped(&[(0.1, 0.05), (0.5, 0.05), (0.9, 0.05)]);
+    assert_eq!(None, one_standard_error(&runs));
+
+    let runs = scored(&[None, None, None]);
+    assert_eq!(None, one_standard_error(&runs));
+}